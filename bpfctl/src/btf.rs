@@ -0,0 +1,389 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+// Copyright Authors of bpfd
+
+//! Client-side BTF introspection for `get`. Rather than round-tripping every
+//! BTF-defined map, function and global through the daemon as new proto
+//! fields, `get` pulls the program's own BTF object straight from the local
+//! kernel by the id `KernelProgramInfo::btf_id` already reports and parses
+//! it locally -- the same data `bpftool btf dump id <id>` shows. The id is
+//! looked up in the kernel bpfctl itself runs on (as `kernel.rs` reads the
+//! local `/proc/sys/kernel/osrelease`), so the result describes bpfd's
+//! program only when bpfctl and bpfd share a host.
+
+use std::{
+    ffi::c_void,
+    io, mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use anyhow::{bail, Context};
+
+const BPF_BTF_GET_FD_BY_ID: i64 = 19;
+const BPF_OBJ_GET_INFO_BY_FD: i64 = 15;
+
+const BTF_KIND_INT: u8 = 1;
+const BTF_KIND_PTR: u8 = 2;
+const BTF_KIND_ARRAY: u8 = 3;
+const BTF_KIND_STRUCT: u8 = 4;
+const BTF_KIND_UNION: u8 = 5;
+const BTF_KIND_ENUM: u8 = 6;
+const BTF_KIND_TYPEDEF: u8 = 8;
+const BTF_KIND_VOLATILE: u8 = 9;
+const BTF_KIND_CONST: u8 = 10;
+const BTF_KIND_RESTRICT: u8 = 11;
+const BTF_KIND_FUNC: u8 = 12;
+const BTF_KIND_FUNC_PROTO: u8 = 13;
+const BTF_KIND_VAR: u8 = 14;
+const BTF_KIND_DATASEC: u8 = 15;
+const BTF_KIND_DECL_TAG: u8 = 17;
+const BTF_KIND_ENUM64: u8 = 19;
+
+#[repr(C)]
+struct GetFdByIdAttr {
+    btf_id: u32,
+}
+
+#[repr(C)]
+struct ObjGetInfoByFdAttr {
+    bpf_fd: u32,
+    info_len: u32,
+    info: u64,
+}
+
+#[repr(C, align(8))]
+#[derive(Default)]
+struct RawBtfInfo {
+    btf: u64,
+    btf_size: u32,
+    id: u32,
+    name: u64,
+    name_len: u32,
+    kernel_btf: u32,
+}
+
+fn bpf_syscall(cmd: i64, attr: *mut c_void, size: u32) -> io::Result<i64> {
+    let ret = unsafe { nix::libc::syscall(nix::libc::SYS_bpf, cmd, attr, size) };
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+fn btf_fd_by_id(id: u32) -> io::Result<OwnedFd> {
+    let mut attr = GetFdByIdAttr { btf_id: id };
+    let fd = bpf_syscall(
+        BPF_BTF_GET_FD_BY_ID,
+        &mut attr as *mut _ as *mut c_void,
+        mem::size_of::<GetFdByIdAttr>() as u32,
+    )?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+/// Fetches the raw BTF blob for `btf_id` from the kernel, growing the
+/// buffer and retrying on an undersized first call -- the kernel reports
+/// the object's true size back rather than erroring, so one retry at that
+/// size always succeeds, the same shape as the verifier-log buffer growth
+/// aya does internally for a failed program load.
+fn btf_bytes_by_id(id: u32) -> io::Result<Vec<u8>> {
+    let fd = btf_fd_by_id(id)?;
+    let mut cap = 4 * 1024u32;
+    loop {
+        let mut buf = vec![0u8; cap as usize];
+        let mut info = RawBtfInfo {
+            btf: buf.as_mut_ptr() as u64,
+            btf_size: cap,
+            ..Default::default()
+        };
+        let mut attr = ObjGetInfoByFdAttr {
+            bpf_fd: fd.as_raw_fd() as u32,
+            info_len: mem::size_of::<RawBtfInfo>() as u32,
+            info: &mut info as *mut _ as u64,
+        };
+        bpf_syscall(
+            BPF_OBJ_GET_INFO_BY_FD,
+            &mut attr as *mut _ as *mut c_void,
+            mem::size_of::<ObjGetInfoByFdAttr>() as u32,
+        )?;
+        if info.btf_size <= cap {
+            buf.truncate(info.btf_size as usize);
+            return Ok(buf);
+        }
+        cap = info.btf_size;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RawType {
+    name_off: u32,
+    kind: u8,
+    vlen: u16,
+    // For most kinds this is `size` (byte size of the type); for kinds
+    // that reference another type (PTR/TYPEDEF/CONST/... /VAR/FUNC) it's
+    // that type's id instead. Same overloaded field the kernel uses.
+    size_or_type: u32,
+    extra_off: usize,
+}
+
+struct Btf {
+    // Index 0 is the implicit `void` type and is never read out of this
+    // vec; real type ids start at 1, matching the kernel's numbering.
+    types: Vec<RawType>,
+    type_bytes: Vec<u8>,
+    strs: Vec<u8>,
+}
+
+/// A BTF-defined map (`SEC(".maps")` global whose type is a struct built
+/// from the `__uint(type, ...)`/`__type(key, ...)`/`__type(value, ...)`
+/// macros) as `bpftool btf dump` would show it.
+pub(crate) struct BtfMap {
+    pub(crate) name: String,
+    pub(crate) map_type: Option<u32>,
+    pub(crate) key_size: Option<u32>,
+    pub(crate) value_size: Option<u32>,
+    pub(crate) max_entries: Option<u32>,
+}
+
+/// A non-map global (`.data`/`.bss`/`.rodata` section member).
+pub(crate) struct BtfGlobal {
+    pub(crate) section: String,
+    pub(crate) name: String,
+    pub(crate) size: u32,
+}
+
+/// Everything `ProgTable::new_get_btf` renders, extracted from one
+/// program's BTF object.
+#[derive(Default)]
+pub(crate) struct ProgramBtfInfo {
+    pub(crate) maps: Vec<BtfMap>,
+    pub(crate) functions: Vec<String>,
+    pub(crate) globals: Vec<BtfGlobal>,
+}
+
+/// Reads and parses the BTF object `btf_id` names, returning `None` for
+/// id `0` -- a program built without BTF info, which is a normal (if
+/// pre-bpf-linker-`--btf`) build rather than an error.
+pub(crate) fn program_btf_info(btf_id: u32) -> anyhow::Result<Option<ProgramBtfInfo>> {
+    if btf_id == 0 {
+        return Ok(None);
+    }
+    let bytes = btf_bytes_by_id(btf_id).context("fetching BTF from the kernel")?;
+    let btf = Btf::parse(&bytes).context("parsing BTF object")?;
+    Ok(Some(btf.program_info()))
+}
+
+impl Btf {
+    fn parse(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() < 8 {
+            bail!("BTF blob too short");
+        }
+        let magic = u16::from_ne_bytes([data[0], data[1]]);
+        if magic != 0xeB9F {
+            bail!("not a BTF blob (bad magic {magic:#x})");
+        }
+        let hdr_len = u32::from_ne_bytes(data[4..8].try_into().unwrap()) as usize;
+        let read_u32 = |off: usize| u32::from_ne_bytes(data[off..off + 4].try_into().unwrap());
+        let type_off = read_u32(8) as usize;
+        let type_len = read_u32(12) as usize;
+        let str_off = read_u32(16) as usize;
+        let str_len = read_u32(20) as usize;
+
+        let type_start = hdr_len + type_off;
+        let type_bytes = data
+            .get(type_start..type_start + type_len)
+            .context("BTF type section out of bounds")?
+            .to_vec();
+        let str_start = hdr_len + str_off;
+        let strs = data
+            .get(str_start..str_start + str_len)
+            .context("BTF string section out of bounds")?
+            .to_vec();
+
+        let mut types = Vec::new();
+        let mut off = 0usize;
+        while off + 12 <= type_bytes.len() {
+            let name_off = u32::from_ne_bytes(type_bytes[off..off + 4].try_into().unwrap());
+            let info = u32::from_ne_bytes(type_bytes[off + 4..off + 8].try_into().unwrap());
+            let size_or_type = u32::from_ne_bytes(type_bytes[off + 8..off + 12].try_into().unwrap());
+            let kind = ((info >> 24) & 0x1f) as u8;
+            let vlen = (info & 0xffff) as u16;
+            let extra_off = off + 12;
+            let extra_len = Self::extra_len(kind, vlen);
+            types.push(RawType {
+                name_off,
+                kind,
+                vlen,
+                size_or_type,
+                extra_off,
+            });
+            off = extra_off + extra_len;
+        }
+
+        Ok(Btf {
+            types,
+            type_bytes,
+            strs,
+        })
+    }
+
+    /// Bytes of kind-specific data following the common 12-byte
+    /// `btf_type` header, so the scan above can skip straight to the next
+    /// type regardless of whether this crate interprets that kind's
+    /// payload.
+    fn extra_len(kind: u8, vlen: u16) -> usize {
+        match kind {
+            BTF_KIND_INT => 4,
+            BTF_KIND_ARRAY => 12,
+            BTF_KIND_STRUCT | BTF_KIND_UNION => vlen as usize * 12,
+            BTF_KIND_ENUM => vlen as usize * 8,
+            BTF_KIND_FUNC_PROTO => vlen as usize * 8,
+            BTF_KIND_VAR => 4,
+            BTF_KIND_DATASEC => vlen as usize * 12,
+            BTF_KIND_DECL_TAG => 4,
+            BTF_KIND_ENUM64 => vlen as usize * 12,
+            _ => 0,
+        }
+    }
+
+    fn name(&self, off: u32) -> String {
+        let start = off as usize;
+        let bytes = self.strs.get(start..).unwrap_or(&[]);
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    }
+
+    /// Type id `0` is the implicit `void` type and isn't stored; real ids
+    /// are 1-based into `self.types`.
+    fn get(&self, id: u32) -> Option<&RawType> {
+        if id == 0 {
+            None
+        } else {
+            self.types.get(id as usize - 1)
+        }
+    }
+
+    fn members(&self, t: &RawType) -> Vec<(String, u32)> {
+        (0..t.vlen as usize)
+            .filter_map(|i| {
+                let off = t.extra_off + i * 12;
+                let bytes = self.type_bytes.get(off..off + 12)?;
+                let name_off = u32::from_ne_bytes(bytes[0..4].try_into().unwrap());
+                let member_type = u32::from_ne_bytes(bytes[4..8].try_into().unwrap());
+                Some((self.name(name_off), member_type))
+            })
+            .collect()
+    }
+
+    /// Strips `const`/`volatile`/`restrict`/`typedef` wrappers to get at
+    /// the underlying type, the same resolution libbpf does before
+    /// inspecting a BTF-defined map's `type`/`key`/`value` members.
+    fn resolve(&self, mut id: u32) -> Option<&RawType> {
+        loop {
+            let t = self.get(id)?;
+            match t.kind {
+                BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST | BTF_KIND_RESTRICT => {
+                    id = t.size_or_type;
+                }
+                _ => return Some(t),
+            }
+        }
+    }
+
+    /// `__uint(type, N)`/`__uint(max_entries, N)` expand to a member whose
+    /// type is `int (*)[N]` -- an array's element count, not its size, is
+    /// where libbpf stashes the value. Returns that `N`.
+    fn array_len_of(&self, member_type: u32) -> Option<u32> {
+        let ptr = self.resolve(member_type)?;
+        if ptr.kind != BTF_KIND_PTR {
+            return None;
+        }
+        let array = self.resolve(ptr.size_or_type)?;
+        if array.kind != BTF_KIND_ARRAY {
+            return None;
+        }
+        let bytes = self.type_bytes.get(array.extra_off..array.extra_off + 12)?;
+        Some(u32::from_ne_bytes(bytes[8..12].try_into().unwrap()))
+    }
+
+    /// `__type(key, T)`/`__type(value, T)` expand to a member of type
+    /// `T *` -- the pointee's byte size is the key/value size.
+    fn pointee_size_of(&self, member_type: u32) -> Option<u32> {
+        let ptr = self.resolve(member_type)?;
+        if ptr.kind != BTF_KIND_PTR {
+            return None;
+        }
+        let pointee = self.resolve(ptr.size_or_type)?;
+        Some(pointee.size_or_type)
+    }
+
+    fn map_from_struct(&self, name: String, struct_type: &RawType) -> BtfMap {
+        let mut map = BtfMap {
+            name,
+            map_type: None,
+            key_size: None,
+            value_size: None,
+            max_entries: None,
+        };
+        for (member_name, member_type) in self.members(struct_type) {
+            match member_name.as_str() {
+                "type" => map.map_type = self.array_len_of(member_type),
+                "max_entries" => map.max_entries = self.array_len_of(member_type),
+                "key" => map.key_size = self.pointee_size_of(member_type),
+                "value" => map.value_size = self.pointee_size_of(member_type),
+                _ => {}
+            }
+        }
+        map
+    }
+
+    fn program_info(&self) -> ProgramBtfInfo {
+        let mut info = ProgramBtfInfo::default();
+
+        for t in &self.types {
+            if t.kind == BTF_KIND_FUNC {
+                info.functions.push(self.name(t.name_off));
+            }
+        }
+
+        for t in &self.types {
+            if t.kind != BTF_KIND_DATASEC {
+                continue;
+            }
+            let section = self.name(t.name_off);
+            let is_maps_section = section == ".maps" || section == "maps";
+
+            for i in 0..t.vlen as usize {
+                let off = t.extra_off + i * 12;
+                let Some(bytes) = self.type_bytes.get(off..off + 12) else {
+                    continue;
+                };
+                let var_type = u32::from_ne_bytes(bytes[0..4].try_into().unwrap());
+                let size = u32::from_ne_bytes(bytes[8..12].try_into().unwrap());
+                let Some(var) = self.get(var_type) else {
+                    continue;
+                };
+                if var.kind != BTF_KIND_VAR {
+                    continue;
+                }
+                let var_name = self.name(var.name_off);
+
+                if is_maps_section {
+                    if let Some(struct_type) = self.resolve(var.size_or_type) {
+                        if struct_type.kind == BTF_KIND_STRUCT {
+                            info.maps.push(self.map_from_struct(var_name, struct_type));
+                            continue;
+                        }
+                    }
+                }
+
+                info.globals.push(BtfGlobal {
+                    section: section.clone(),
+                    name: var_name,
+                    size,
+                });
+            }
+        }
+
+        info
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+// Copyright Authors of bpfd
+
+//! Host kernel version detection, used to validate program requirements
+//! client-side before they're sent to bpfd, and to annotate `list`/`get`
+//! output with the running kernel.
+
+use std::{fmt, sync::OnceLock};
+
+use crate::LoadCommands;
+
+static RUNNING_KERNEL: OnceLock<Option<KernelVersion>> = OnceLock::new();
+
+/// Returns the process-lifetime cache of the running kernel's version,
+/// detecting it on first access.
+pub(crate) fn running_kernel() -> Option<KernelVersion> {
+    *RUNNING_KERNEL.get_or_init(detect)
+}
+
+/// A parsed `/proc/sys/kernel/osrelease` version, e.g. "6.5.0-generic" ->
+/// `KernelVersion { major: 6, minor: 5, patch: 0 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct KernelVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Parses the running kernel's version out of `/proc/sys/kernel/osrelease`,
+/// returning `None` if the file is missing or its contents don't start with
+/// a `major.minor[.patch]` version (a missing patch reads as `0`), rather
+/// than erroring. Modern loaders (e.g. libbpf)
+/// treat kernel_version as optional for exactly this reason.
+pub(crate) fn detect() -> Option<KernelVersion> {
+    let osrelease = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    parse(osrelease.trim())
+}
+
+fn parse(version: &str) -> Option<KernelVersion> {
+    let base = version.split(['-', '+']).next()?;
+    let mut parts = base.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next().unwrap_or("0").parse().unwrap_or(0);
+    Some(KernelVersion {
+        major,
+        minor,
+        patch,
+    })
+}
+
+/// The minimum kernel version bpfd requires to support `command`, if any is
+/// known. `None` means either "no special requirement" or "not tracked yet".
+pub(crate) fn minimum_required(command: &LoadCommands) -> Option<KernelVersion> {
+    match command {
+        LoadCommands::Usdt { .. } => Some(KernelVersion {
+            major: 4,
+            minor: 20,
+            patch: 0,
+        }),
+        LoadCommands::Fentry { .. } | LoadCommands::Fexit { .. } => Some(KernelVersion {
+            major: 5,
+            minor: 5,
+            patch: 0,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dotted_triple_with_suffix() {
+        assert_eq!(
+            parse("6.5.0-generic"),
+            Some(KernelVersion {
+                major: 6,
+                minor: 5,
+                patch: 0
+            })
+        );
+    }
+
+    #[test]
+    fn parses_missing_patch() {
+        assert_eq!(
+            parse("5.8"),
+            Some(KernelVersion {
+                major: 5,
+                minor: 8,
+                patch: 0
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unparseable_version() {
+        assert_eq!(parse("not-a-version"), None);
+    }
+}
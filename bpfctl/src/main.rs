@@ -10,10 +10,11 @@ use bpfd_api::{
     util::directories::*,
     v1::{
         attach_info::Info, bpfd_client::BpfdClient, bytecode_location::Location,
-        list_response::ListResult, AttachInfo, BytecodeImage, BytecodeLocation, GetRequest,
-        KernelProgramInfo, KprobeAttachInfo, ListRequest, LoadRequest, ProgramInfo,
-        PullBytecodeRequest, TcAttachInfo, TracepointAttachInfo, UnloadRequest, UprobeAttachInfo,
-        XdpAttachInfo,
+        list_response::ListResult, AttachInfo, AttachRequest, BytecodeImage, BytecodeLocation,
+        DetachRequest, FentryAttachInfo, FexitAttachInfo, GetLogsRequest, GetRequest,
+        KernelProgramInfo, KprobeAttachInfo, ListRequest, LoadRequest, LogRecord,
+        PerfEventAttachInfo, ProgramInfo, PullBytecodeRequest, TcAttachInfo, TracepointAttachInfo,
+        UnloadRequest, UprobeAttachInfo, UsdtAttachInfo, VersionRequest, XdpAttachInfo,
     },
     ImagePullPolicy,
     ProbeType::*,
@@ -24,14 +25,29 @@ use comfy_table::{Cell, Color, Table};
 use hex::{encode_upper, FromHex};
 use log::{info, warn};
 use tokio::net::UnixStream;
+use tokio_stream::StreamExt;
 use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Uri};
 use tower::service_fn;
 
+mod btf;
+mod kernel;
+
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
     #[clap(subcommand)]
     command: Commands,
+
+    /// Output format for `load-from-file`, `load-from-image`, `list` and
+    /// `get` results, and for errors.
+    #[clap(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
 }
 
 #[derive(Subcommand)]
@@ -42,6 +58,10 @@ enum Commands {
     LoadFromImage(LoadImageArgs),
     /// Unload an eBPF program using the program id.
     Unload(UnloadArgs),
+    /// Attach a loaded eBPF program to a hook point, returning a link id.
+    Attach(AttachArgs),
+    /// Detach an active link from a loaded eBPF program using its link id.
+    Detach(DetachArgs),
     /// List all eBPF programs loaded via bpfd.
     List(ListArgs),
     /// Get an eBPF program using the program id.
@@ -51,6 +71,16 @@ enum Commands {
     },
     /// Pull a bytecode image for future use by a load command.
     PullBytecode(PullBytecodeArgs),
+    /// Stream log records emitted by a loaded eBPF program.
+    Logs {
+        /// Required: Program id to stream logs for.
+        id: u32,
+
+        /// Optional: Keep streaming new records instead of exiting once the
+        /// currently buffered records have been printed.
+        #[clap(short, long)]
+        follow: bool,
+    },
 }
 
 #[derive(Args)]
@@ -277,6 +307,77 @@ enum LoadCommands {
         #[clap(short, long)]
         namespace: Option<String>,
     },
+    /// Install an eBPF program on a USDT (user statically-defined tracepoint)
+    Usdt {
+        /// Required: Library name or the absolute path to a binary or library
+        /// containing the USDT probe.
+        /// Example: --target "libc".
+        #[clap(short, long, verbatim_doc_comment)]
+        target: String,
+
+        /// Required: USDT provider name.
+        #[clap(long)]
+        provider: String,
+
+        /// Required: USDT probe name.
+        #[clap(long)]
+        probe: String,
+
+        /// Optional: Only execute the USDT probe for given process identification
+        /// number (PID). If PID is not provided, the probe executes for all PIDs.
+        #[clap(short, long, verbatim_doc_comment)]
+        pid: Option<i32>,
+
+        /// Optional: Cookie value passed through to the attached program, readable
+        /// via `bpf_get_attach_cookie()`.
+        #[clap(long, verbatim_doc_comment)]
+        cookie: Option<u64>,
+
+        /// Optional: Namespace to attach the USDT probe in. (NOT CURRENTLY SUPPORTED)
+        #[clap(short, long)]
+        namespace: Option<String>,
+    },
+    /// Install an eBPF program that runs before a kernel function (fentry).
+    Fentry {
+        /// Required: BTF-enabled kernel function to attach to.
+        #[clap(short, long)]
+        fn_name: String,
+    },
+    /// Install an eBPF program that runs after a kernel function returns (fexit).
+    Fexit {
+        /// Required: BTF-enabled kernel function to attach to.
+        #[clap(short, long)]
+        fn_name: String,
+    },
+    /// Install an eBPF program on a perf event, for sampling profilers.
+    PerfEvent {
+        /// Required: The perf event category to sample.
+        ///
+        /// [possible values: hardware, software, raw-tracepoint]
+        #[clap(short, long, verbatim_doc_comment)]
+        event_type: String,
+
+        /// Required: The event to sample within `event_type`.
+        /// Example: --event-config "cpu-cycles", --event-config "task-clock".
+        #[clap(short = 'c', long, verbatim_doc_comment)]
+        event_config: String,
+
+        /// Optional: Sample every N occurrences of the event.
+        /// Mutually exclusive with --sample-frequency.
+        #[clap(long, verbatim_doc_comment, conflicts_with = "sample_frequency")]
+        sample_period: Option<u64>,
+
+        /// Optional: Sample at this frequency (Hz) instead of a fixed period.
+        /// Mutually exclusive with --sample-period.
+        #[clap(long, verbatim_doc_comment, conflicts_with = "sample_period")]
+        sample_frequency: Option<u64>,
+
+        /// Optional: Only open the perf event on these CPUs.
+        /// If not provided, the program attaches on every online CPU.
+        /// Example: --cpus 0,1,2,3
+        #[clap(long, verbatim_doc_comment, num_args(1..), value_delimiter = ',')]
+        cpus: Option<Vec<u32>>,
+    },
 }
 
 #[derive(Args)]
@@ -285,6 +386,24 @@ struct UnloadArgs {
     id: u32,
 }
 
+#[derive(Args)]
+struct AttachArgs {
+    /// Required: Program id of the loaded eBPF program to attach.
+    id: u32,
+
+    #[clap(subcommand)]
+    command: LoadCommands,
+}
+
+#[derive(Args)]
+struct DetachArgs {
+    /// Required: Program id the link was created against.
+    id: u32,
+
+    /// Required: Link id returned by the attach command.
+    link_id: u32,
+}
+
 #[derive(Args)]
 struct PullBytecodeArgs {
     /// Required: Container Image URL.
@@ -336,6 +455,161 @@ struct GlobalArg {
     value: Vec<u8>,
 }
 
+/// Renders a loaded program's bpfd-side state as JSON, mirroring the fields
+/// `ProgTable::new_get_bpfd` prints as a table.
+fn program_info_json(info: &ProgramInfo) -> serde_json::Value {
+    let bytecode = match info.bytecode.clone().and_then(|b| b.location) {
+        Some(Location::Image(i)) => serde_json::json!({
+            "image_url": i.url,
+            "pull_policy": TryInto::<ImagePullPolicy>::try_into(i.image_pull_policy)
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+        }),
+        Some(Location::File(p)) => serde_json::json!({ "path": p }),
+        None => serde_json::Value::Null,
+    };
+
+    serde_json::json!({
+        "name": info.name,
+        "bytecode": bytecode,
+        "global_data": info
+            .global_data
+            .iter()
+            .map(|(k, v)| (k.clone(), encode_upper(v)))
+            .collect::<HashMap<_, _>>(),
+        "metadata": info.metadata,
+        "map_pin_path": info.map_pin_path,
+        "map_owner_id": info.map_owner_id,
+        "map_used_by": info.map_used_by,
+        "attach": info.attach.as_ref().map(attach_info_json),
+    })
+}
+
+/// Renders the attach-specific fields printed per-variant in
+/// `ProgTable::new_get_bpfd` as a single tagged JSON object.
+fn attach_info_json(attach: &AttachInfo) -> serde_json::Value {
+    match attach.info.clone() {
+        Some(Info::XdpAttachInfo(i)) => serde_json::json!({
+            "type": "xdp", "priority": i.priority, "iface": i.iface,
+            "position": i.position, "proceed_on": i.proceed_on,
+        }),
+        Some(Info::TcAttachInfo(i)) => serde_json::json!({
+            "type": "tc", "priority": i.priority, "iface": i.iface, "position": i.position,
+            "direction": i.direction, "proceed_on": i.proceed_on,
+        }),
+        Some(Info::TracepointAttachInfo(i)) => serde_json::json!({
+            "type": "tracepoint", "tracepoint": i.tracepoint,
+        }),
+        Some(Info::KprobeAttachInfo(i)) => serde_json::json!({
+            "type": "kprobe", "fn_name": i.fn_name, "offset": i.offset,
+            "retprobe": i.retprobe, "namespace": i.namespace,
+        }),
+        Some(Info::UprobeAttachInfo(i)) => serde_json::json!({
+            "type": "uprobe", "fn_name": i.fn_name, "offset": i.offset, "target": i.target,
+            "retprobe": i.retprobe, "pid": i.pid, "namespace": i.namespace,
+        }),
+        Some(Info::UsdtAttachInfo(i)) => serde_json::json!({
+            "type": "usdt", "target": i.binary_path, "provider": i.provider,
+            "probe": i.name, "pid": i.pid, "cookie": i.cookie, "namespace": i.namespace,
+        }),
+        Some(Info::FentryAttachInfo(i)) => serde_json::json!({ "type": "fentry", "fn_name": i.fn_name }),
+        Some(Info::FexitAttachInfo(i)) => serde_json::json!({ "type": "fexit", "fn_name": i.fn_name }),
+        Some(Info::PerfEventAttachInfo(i)) => serde_json::json!({
+            "type": "perf_event", "event_type": i.event_type, "event_config": i.event_config,
+            "sample_period": i.sample_period, "sample_frequency": i.sample_frequency, "cpus": i.cpus,
+        }),
+        None => serde_json::Value::Null,
+    }
+}
+
+/// Renders a program's kernel-side state as JSON, mirroring the fields
+/// `ProgTable::new_get_unsupported` prints as a table.
+fn kernel_info_json(info: &KernelProgramInfo) -> serde_json::Value {
+    serde_json::json!({
+        "running_kernel": kernel::running_kernel()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+        "id": info.id,
+        "name": info.name,
+        "program_type": ProgramType::try_from(info.program_type)
+            .map(|t| t.to_string())
+            .unwrap_or_default(),
+        "loaded_at": info.loaded_at,
+        "tag": info.tag,
+        "gpl_compatible": info.gpl_compatible,
+        "map_ids": info.map_ids,
+        "btf_id": info.btf_id,
+        "bytes_xlated": info.bytes_xlated,
+        "jited": info.jited,
+        "bytes_jited": info.bytes_jited,
+        "bytes_memlock": info.bytes_memlock,
+        "verified_insns": info.verified_insns,
+        "verifier_log": info.verifier_log,
+    })
+}
+
+/// Combines a program's bpfd and kernel state into the JSON object emitted
+/// by `get`, and by each element of `list`'s array.
+fn program_json(info: &Option<ProgramInfo>, kernel_info: &Option<KernelProgramInfo>) -> serde_json::Value {
+    serde_json::json!({
+        "program": info.as_ref().map(program_info_json),
+        "kernel": kernel_info.as_ref().map(kernel_info_json),
+    })
+}
+
+/// Prints a single program's state in the requested `output` format, used by
+/// `load`/`get` which each return exactly one program.
+fn print_program(
+    info: &Option<ProgramInfo>,
+    kernel_info: &Option<KernelProgramInfo>,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
+    // Best-effort: a program with no BTF (btf_id 0), or bpfctl running
+    // without CAP_BPF/CAP_SYS_ADMIN to read it back, just means no third
+    // section rather than a failed `get`.
+    let btf_info = kernel_info
+        .as_ref()
+        .and_then(|k| btf::program_btf_info(k.btf_id).ok().flatten());
+
+    match output {
+        OutputFormat::Json => {
+            let mut v = program_json(info, kernel_info);
+            if let Some(btf_info) = &btf_info {
+                v["btf"] = btf_info_json(btf_info);
+            }
+            println!("{}", serde_json::to_string_pretty(&v)?);
+        }
+        OutputFormat::Table => {
+            ProgTable::new_get_bpfd(info)?.print();
+            ProgTable::new_get_unsupported(kernel_info)?.print();
+            if let Some(btf_info) = &btf_info {
+                ProgTable::new_get_btf(btf_info).print();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders a program's BTF-derived maps, functions and globals as JSON,
+/// mirroring the fields `ProgTable::new_get_btf` prints as a table.
+fn btf_info_json(info: &btf::ProgramBtfInfo) -> serde_json::Value {
+    serde_json::json!({
+        "maps": info.maps.iter().map(|m| serde_json::json!({
+            "name": m.name,
+            "map_type": m.map_type,
+            "key_size": m.key_size,
+            "value_size": m.value_size,
+            "max_entries": m.max_entries,
+        })).collect::<Vec<_>>(),
+        "functions": info.functions,
+        "globals": info.globals.iter().map(|g| serde_json::json!({
+            "section": g.section,
+            "name": g.name,
+            "size": g.size,
+        })).collect::<Vec<_>>(),
+    })
+}
+
 struct ProgTable(Table);
 
 impl ProgTable {
@@ -512,6 +786,45 @@ impl ProgTable {
                     table.add_row(vec!["PID", &pid.unwrap_or(0).to_string()]);
                     table.add_row(vec!["Namespace", &namespace.unwrap_or("".to_string())]);
                 }
+                Info::UsdtAttachInfo(UsdtAttachInfo {
+                    binary_path,
+                    provider,
+                    name,
+                    pid,
+                    cookie,
+                    namespace,
+                }) => {
+                    table.add_row(vec!["Target:", &binary_path]);
+                    table.add_row(vec!["Provider:", &provider]);
+                    table.add_row(vec!["Probe:", &name]);
+                    table.add_row(vec!["PID", &pid.unwrap_or(0).to_string()]);
+                    table.add_row(vec!["Cookie", &cookie.unwrap_or(0).to_string()]);
+                    table.add_row(vec!["Namespace", &namespace.unwrap_or("".to_string())]);
+                }
+                Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => {
+                    table.add_row(vec!["Function Name:", &fn_name]);
+                }
+                Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => {
+                    table.add_row(vec!["Function Name:", &fn_name]);
+                }
+                Info::PerfEventAttachInfo(PerfEventAttachInfo {
+                    event_type,
+                    event_config,
+                    sample_period,
+                    sample_frequency,
+                    cpus,
+                }) => {
+                    let sample = match (sample_period, sample_frequency) {
+                        (Some(period), _) => format!("period={period}"),
+                        (_, Some(freq)) => format!("frequency={freq}Hz"),
+                        _ => "None".to_string(),
+                    };
+
+                    table.add_row(vec!["Event Type:", &event_type]);
+                    table.add_row(vec!["Event Config:", &event_config]);
+                    table.add_row(vec!["Sample:", &sample]);
+                    table.add_row(vec!["CPU FDs:", &cpus.len().to_string()]);
+                }
             }
         }
 
@@ -539,7 +852,12 @@ impl ProgTable {
             kernel_info.name.clone()
         };
 
+        let running_kernel = kernel::running_kernel()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
         let rows = vec![
+            vec!["Running Kernel:".to_string(), running_kernel],
             vec!["ID:".to_string(), kernel_info.id.to_string()],
             vec!["Name:".to_string(), name],
             vec![
@@ -574,9 +892,92 @@ impl ProgTable {
         ];
         table.add_rows(rows);
 
+        if !kernel_info.verifier_log.is_empty() {
+            table.add_row(vec!["Verifier Log:".to_string(), kernel_info.verifier_log]);
+        }
+
         Ok(ProgTable(table))
     }
-    
+
+    /// Renders the maps, BTF function names and `.data`/`.bss`/`.rodata`
+    /// globals found by walking the program's own BTF object -- the same
+    /// information `bpftool btf dump` exposes, scoped to just this
+    /// program's get.
+    fn new_get_btf(info: &btf::ProgramBtfInfo) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("BTF")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+
+        if info.maps.is_empty() && info.functions.is_empty() && info.globals.is_empty() {
+            table.add_row(vec!["NONE"]);
+            return ProgTable(table);
+        }
+
+        if info.maps.is_empty() {
+            table.add_row(vec!["Maps:", "None"]);
+        } else {
+            let mut first = true;
+            for m in &info.maps {
+                let label = if first {
+                    first = false;
+                    "Maps:"
+                } else {
+                    ""
+                };
+                let map_type = m
+                    .map_type
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                let key = m.key_size.map(|s| s.to_string()).unwrap_or_else(|| "?".to_string());
+                let value = m
+                    .value_size
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "?".to_string());
+                let max_entries = m
+                    .max_entries
+                    .map(|e| e.to_string())
+                    .unwrap_or_else(|| "?".to_string());
+                table.add_row(vec![
+                    label.to_string(),
+                    format!(
+                        "{} (type={map_type}, key_size={key}, value_size={value}, max_entries={max_entries})",
+                        m.name
+                    ),
+                ]);
+            }
+        }
+
+        if info.functions.is_empty() {
+            table.add_row(vec!["Functions:", "None"]);
+        } else {
+            table.add_row(vec!["Functions:", &info.functions.join(", ")]);
+        }
+
+        if info.globals.is_empty() {
+            table.add_row(vec!["Globals:", "None"]);
+        } else {
+            let mut first = true;
+            for g in &info.globals {
+                let label = if first {
+                    first = false;
+                    "Globals:"
+                } else {
+                    ""
+                };
+                table.add_row(vec![
+                    label.to_string(),
+                    format!("{}.{} ({} bytes)", g.section, g.name, g.size),
+                ]);
+            }
+        }
+
+        ProgTable(table)
+    }
+
     fn new_list_xdp(&mut self) {
         let mut table = Table::new();
 
@@ -641,12 +1042,12 @@ impl ProgTable {
         .fg(Color::Green)]);
 
         table.load_preset(comfy_table::presets::NOTHING);
-        table.set_header(vec!["Program ID", "Name", "Function_name", "Offset", "Target", "Retprobe", "Pid"]);
+        table.set_header(vec!["Program ID", "Name", "Function_name", "Offset", "Target", "Retprobe", "Pid", "Namespace"]);
         ProgTable(table)
     }
 
-    fn add_row_list_uprobe(&mut self, id: String, name: String, fn_name: String, offset: String, target: String, retprobe: String, pid: Option<String>) {
-        self.0.add_row(vec![id, name, fn_name, offset, target, retprobe, pid.unwrap_or("None".to_string())]);
+    fn add_row_list_uprobe(&mut self, id: String, name: String, fn_name: String, offset: String, target: String, retprobe: String, pid: Option<String>, namespace: Option<String>) {
+        self.0.add_row(vec![id, name, fn_name, offset, target, retprobe, pid.unwrap_or("None".to_string()), namespace.unwrap_or("None".to_string())]);
     }
 
     fn new_list_kprobe(&mut self) {
@@ -658,12 +1059,29 @@ impl ProgTable {
         .fg(Color::Green)]);
 
         table.load_preset(comfy_table::presets::NOTHING);
-        table.set_header(vec!["Program ID", "Name", "Function_name", "Offset", "Retprobe"]);
+        table.set_header(vec!["Program ID", "Name", "Function_name", "Offset", "Retprobe", "Namespace"]);
         ProgTable(table)
     }
 
-    fn add_row_list_kprobe(&mut self, id: String, name: String, fn_name: String, offset: String, retprobe: String) {
-        self.0.add_row(vec![id, name, fn_name, offset, retprobe]);
+    fn add_row_list_kprobe(&mut self, id: String, name: String, fn_name: String, offset: String, retprobe: String, namespace: Option<String>) {
+        self.0.add_row(vec![id, name, fn_name, offset, retprobe, namespace.unwrap_or("None".to_string())]);
+    }
+
+    fn new_list_usdt(&mut self) {
+        let mut table = Table::new();
+
+        table.set_header(vec![Cell::new("USDT Programs")
+        .add_attribute(comfy_table::Attribute::Bold)
+        .add_attribute(comfy_table::Attribute::Underlined)
+        .fg(Color::Green)]);
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec!["Program ID", "Name", "Target", "Provider", "Probe", "Pid"]);
+        ProgTable(table)
+    }
+
+    fn add_row_list_usdt(&mut self, id: String, name: String, target: String, provider: String, probe: String, pid: Option<String>) {
+        self.0.add_row(vec![id, name, target, provider, probe, pid.unwrap_or("None".to_string())]);
     }
 
     fn new_list_all(&mut self, type_: String) {
@@ -703,6 +1121,10 @@ impl LoadCommands {
             LoadCommands::Tracepoint { .. } => ProgramType::Tracepoint,
             LoadCommands::Kprobe { .. } => ProgramType::Probe,
             LoadCommands::Uprobe { .. } => ProgramType::Probe,
+            LoadCommands::Usdt { .. } => ProgramType::Probe,
+            LoadCommands::Fentry { .. } => ProgramType::Tracing,
+            LoadCommands::Fexit { .. } => ProgramType::Tracing,
+            LoadCommands::PerfEvent { .. } => ProgramType::PerfEvent,
         }
     }
 
@@ -761,9 +1183,6 @@ impl LoadCommands {
                 retprobe,
                 namespace,
             } => {
-                if namespace.is_some() {
-                    bail!("kprobe namespace option not supported yet");
-                }
                 let offset = offset.unwrap_or(0);
                 Ok(Some(AttachInfo {
                     info: Some(Info::KprobeAttachInfo(KprobeAttachInfo {
@@ -782,9 +1201,6 @@ impl LoadCommands {
                 pid,
                 namespace,
             } => {
-                if namespace.is_some() {
-                    bail!("uprobe namespace option not supported yet");
-                }
                 let offset = offset.unwrap_or(0);
                 Ok(Some(AttachInfo {
                     info: Some(Info::UprobeAttachInfo(UprobeAttachInfo {
@@ -797,6 +1213,58 @@ impl LoadCommands {
                     })),
                 }))
             }
+            LoadCommands::Usdt {
+                target,
+                provider,
+                probe,
+                pid,
+                cookie,
+                namespace,
+            } => {
+                if namespace.is_some() {
+                    bail!("usdt namespace option not supported yet");
+                }
+                Ok(Some(AttachInfo {
+                    info: Some(Info::UsdtAttachInfo(UsdtAttachInfo {
+                        binary_path: target.clone(),
+                        provider: provider.clone(),
+                        name: probe.clone(),
+                        pid: *pid,
+                        cookie: *cookie,
+                        namespace: namespace.clone(),
+                    })),
+                }))
+            }
+            LoadCommands::Fentry { fn_name } => Ok(Some(AttachInfo {
+                info: Some(Info::FentryAttachInfo(FentryAttachInfo {
+                    fn_name: fn_name.to_string(),
+                })),
+            })),
+            LoadCommands::Fexit { fn_name } => Ok(Some(AttachInfo {
+                info: Some(Info::FexitAttachInfo(FexitAttachInfo {
+                    fn_name: fn_name.to_string(),
+                })),
+            })),
+            LoadCommands::PerfEvent {
+                event_type,
+                event_config,
+                sample_period,
+                sample_frequency,
+                cpus,
+            } => {
+                if sample_period.is_none() && sample_frequency.is_none() {
+                    bail!("one of --sample-period or --sample-frequency is required");
+                }
+                Ok(Some(AttachInfo {
+                    info: Some(Info::PerfEventAttachInfo(PerfEventAttachInfo {
+                        event_type: event_type.to_string(),
+                        event_config: event_config.to_string(),
+                        sample_period: *sample_period,
+                        sample_frequency: *sample_frequency,
+                        cpus: cpus.clone().unwrap_or_default(),
+                    })),
+                }))
+            }
         }
     }
 }
@@ -823,6 +1291,22 @@ impl Commands {
             _ => bail!("Unknown command"),
         }
     }
+
+    /// Named optional daemon capability this command depends on, if any, so
+    /// it can be refused locally when the negotiated [`Capabilities`] don't
+    /// advertise support instead of round-tripping a request bpfd will reject.
+    fn required_capability(&self) -> Option<&'static str> {
+        match self {
+            Commands::LoadFromFile(l) => {
+                matches!(l.command, LoadCommands::Usdt { .. }).then_some("usdt")
+            }
+            Commands::LoadFromImage(l) => {
+                matches!(l.command, LoadCommands::Usdt { .. }).then_some("usdt")
+            }
+            Commands::Logs { .. } => Some("log streaming"),
+            _ => None,
+        }
+    }
 }
 
 /// Parse a single key-value pair
@@ -910,25 +1394,115 @@ async fn main() -> anyhow::Result<()> {
                 address,
                 port,
                 enabled: _,
-            } => match execute_request_tcp(&cli.command, address, port, tls_config.clone()).await {
+            } => match execute_request_tcp(&cli.command, address, port, tls_config.clone(), cli.output).await {
                 Ok(_) => return Ok(()),
-                Err(e) => eprintln!("Error = {e:?}"),
+                Err(e) => print_error(&e, cli.output),
             },
             config::Endpoint::Unix { path, enabled } if !enabled => {
                 info!("Skipping disabled endpoint on {path}")
             }
             config::Endpoint::Unix { path, enabled: _ } => {
-                match execute_request_unix(&cli.command, path).await {
+                match execute_request_unix(&cli.command, path, cli.output).await {
                     Ok(_) => return Ok(()),
-                    Err(e) => eprintln!("Error = {e:?}"),
+                    Err(e) => print_error(&e, cli.output),
                 }
             }
         }
     }
-    bail!("Failed to execute request")
+    print_error(&anyhow::anyhow!("Failed to execute request"), cli.output);
+    std::process::exit(1)
+}
+
+/// Prints a command failure as a plain message or, under `--output json`, as
+/// a stable `{"error": "..."}` object so callers never have to parse mixed
+/// stderr/stdout.
+fn print_error(e: &anyhow::Error, output: OutputFormat) {
+    match output {
+        OutputFormat::Json => {
+            let value = serde_json::json!({ "error": e.to_string() });
+            eprintln!("{}", serde_json::to_string(&value).unwrap_or_default());
+        }
+        OutputFormat::Table => eprintln!("Error = {e:?}"),
+    }
+}
+
+/// bpfctl's own protocol version. Only the major component is checked
+/// against the daemon's, matching semver-style breaking-change semantics.
+const PROTOCOL_VERSION: &str = "1.0.0";
+
+fn protocol_major(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
+/// Optional daemon features advertised over the `version` handshake, keyed
+/// by the names checked in [`Commands::required_capability`].
+struct Capabilities {
+    usdt_supported: bool,
+    log_streaming_supported: bool,
+}
+
+impl Capabilities {
+    fn supports(&self, name: &str) -> bool {
+        match name {
+            "usdt" => self.usdt_supported,
+            "log streaming" => self.log_streaming_supported,
+            _ => true,
+        }
+    }
 }
 
-async fn execute_request_unix(command: &Commands, path: String) -> anyhow::Result<()> {
+/// Performs the `version` handshake, bailing if bpfctl and bpfd disagree on
+/// major protocol version, and returns the daemon's advertised capabilities.
+async fn negotiate_version(client: &mut BpfdClient<Channel>) -> anyhow::Result<Capabilities> {
+    let request = tonic::Request::new(VersionRequest {
+        client_version: PROTOCOL_VERSION.to_string(),
+    });
+    let response = client.version(request).await?.into_inner();
+
+    let (client_major, daemon_major) = (
+        protocol_major(PROTOCOL_VERSION),
+        protocol_major(&response.version),
+    );
+    if client_major != daemon_major {
+        bail!(
+            "bpfctl protocol v{client_major} is incompatible with bpfd protocol v{daemon_major}; \
+             upgrade one side to match the other"
+        );
+    }
+
+    Ok(Capabilities {
+        usdt_supported: response.usdt_supported,
+        log_streaming_supported: response.log_streaming_supported,
+    })
+}
+
+/// Warns (without blocking the request) when `command` declares a minimum
+/// kernel version that the detected running kernel doesn't meet. A host
+/// kernel that can't be detected is treated as "unknown", not a failure,
+/// since bpfd may still be running on a remote host newer than this one.
+fn warn_if_kernel_too_old(command: &Commands) {
+    let load = match command {
+        Commands::LoadFromFile(l) => &l.command,
+        Commands::LoadFromImage(l) => &l.command,
+        _ => return,
+    };
+
+    if let (Some(required), Some(running)) = (kernel::minimum_required(load), kernel::running_kernel())
+    {
+        if running < required {
+            warn!(
+                "this program declares a minimum kernel version of {required}, \
+                 but the running kernel is {running}; bpfd may reject the load"
+            );
+        }
+    }
+}
+
+async fn execute_request_unix(
+    command: &Commands,
+    path: String,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
     // Format address to something like: "unix://run/bpfd/bpfd.sock"
     let address = format!("unix:/{path}");
     let channel = Endpoint::try_from(address)?
@@ -936,7 +1510,7 @@ async fn execute_request_unix(command: &Commands, path: String) -> anyhow::Resul
         .await?;
 
     info!("Using UNIX socket as transport");
-    execute_request(command, channel).await
+    execute_request(command, channel, output).await
 }
 
 async fn execute_request_tcp(
@@ -944,6 +1518,7 @@ async fn execute_request_tcp(
     address: String,
     port: u16,
     tls_config: ClientTlsConfig,
+    output: OutputFormat,
 ) -> anyhow::Result<()> {
     let address = SocketAddr::new(
         address
@@ -959,11 +1534,24 @@ async fn execute_request_tcp(
         .await?;
 
     info!("Using TLS over TCP socket as transport");
-    execute_request(command, channel).await
+    execute_request(command, channel, output).await
 }
 
-async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result<()> {
+async fn execute_request(
+    command: &Commands,
+    channel: Channel,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
     let mut client = BpfdClient::new(channel);
+
+    let capabilities = negotiate_version(&mut client).await?;
+    if let Some(capability) = command.required_capability() {
+        if !capabilities.supports(capability) {
+            bail!("bpfd does not support '{capability}'; refusing to send a request it would reject");
+        }
+    }
+    warn_if_kernel_too_old(command);
+
     match command {
         Commands::LoadFromFile(l) => {
             let bytecode = match command.get_bytecode_location() {
@@ -994,8 +1582,7 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
             });
             let response = client.load(request).await?.into_inner();
 
-            ProgTable::new_get_bpfd(&response.info)?.print();
-            ProgTable::new_get_unsupported(&response.kernel_info)?.print();
+            print_program(&response.info, &response.kernel_info, output)?;
         }
 
         Commands::LoadFromImage(l) => {
@@ -1027,14 +1614,33 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
             });
             let response = client.load(request).await?.into_inner();
 
-            ProgTable::new_get_bpfd(&response.info)?.print();
-            ProgTable::new_get_unsupported(&response.kernel_info)?.print();
+            print_program(&response.info, &response.kernel_info, output)?;
         }
 
         Commands::Unload(l) => {
             let request = tonic::Request::new(UnloadRequest { id: l.id });
             let _response = client.unload(request).await?.into_inner();
         }
+        Commands::Attach(a) => {
+            let attach = match a.command.get_attach_type() {
+                Ok(t) => t,
+                Err(e) => bail!(e),
+            };
+
+            let request = tonic::Request::new(AttachRequest { id: a.id, attach });
+            let response = client.attach(request).await?.into_inner();
+
+            println!("Attached link id {}", response.link_id);
+        }
+        Commands::Detach(d) => {
+            let request = tonic::Request::new(DetachRequest {
+                id: d.id,
+                link_id: d.link_id,
+            });
+            let _response = client.detach(request).await?;
+
+            println!("Detached link id {}", d.link_id);
+        }
         Commands::List(l) => {
             let prog_type_filter = l.program_type.map(|p| p as u32);
 
@@ -1051,6 +1657,17 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                 bpfd_programs_only: Some(!l.all),
             });
             let response = client.list(request).await?.into_inner();
+
+            if output == OutputFormat::Json {
+                let programs: Vec<serde_json::Value> = response
+                    .results
+                    .iter()
+                    .map(|r| program_json(&r.info, &r.kernel_info))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&programs)?);
+                return Ok(());
+            }
+
             let mut table = ProgTable(Table::new());
             let mut prog_buckets: HashMap<String, Vec<ListResult>> = HashMap::new();
 
@@ -1112,11 +1729,47 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                         table.print();
                     }
                     ProgramType::Probe => {
-                        let mut table = ProgTable::new_list_kprobe();
-                        for r in v.into_iter() { 
+                        // Kprobe, Uprobe, and Usdt programs all report as
+                        // ProgramType::Probe, so split this bucket back out
+                        // by attach info variant before rendering.
+                        let mut kprobe_table = ProgTable::new_list_kprobe();
+                        let mut uprobe_table = ProgTable::new_list_uprobe();
+                        let mut usdt_table = ProgTable::new_list_usdt();
+                        for r in v.into_iter() {
                             let info = r.info.unwrap();
-                            let kprobe_info = match info.attach.unwrap().info
+                            match info.attach.unwrap().info.unwrap() {
+                                Info::KprobeAttachInfo(k) => kprobe_table.add_row_list_kprobe(
+                                    r.id,
+                                    r.name,
+                                    k.fn_name,
+                                    k.offset.to_string(),
+                                    k.retprobe.to_string(),
+                                    k.namespace,
+                                ),
+                                Info::UprobeAttachInfo(u) => uprobe_table.add_row_list_uprobe(
+                                    r.id,
+                                    r.name,
+                                    u.fn_name.unwrap_or_default(),
+                                    u.offset.to_string(),
+                                    u.target,
+                                    u.retprobe.to_string(),
+                                    u.pid.map(|p| p.to_string()),
+                                    u.namespace,
+                                ),
+                                Info::UsdtAttachInfo(u) => usdt_table.add_row_list_usdt(
+                                    r.id,
+                                    r.name,
+                                    u.binary_path,
+                                    u.provider,
+                                    u.name,
+                                    u.pid.map(|p| p.to_string()),
+                                ),
+                                _ => bail!("Invalid attach info for probe program"),
+                            }
                         }
+                        kprobe_table.print();
+                        uprobe_table.print();
+                        usdt_table.print();
                     }
                     _ => { 
 
@@ -1130,8 +1783,7 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
             let request = tonic::Request::new(GetRequest { id: *id });
             let response = client.get(request).await?.into_inner();
 
-            ProgTable::new_get_bpfd(&response.info)?.print();
-            ProgTable::new_get_unsupported(&response.kernel_info)?.print();
+            print_program(&response.info, &response.kernel_info, output)?;
         }
         Commands::PullBytecode(l) => {
             let image: BytecodeImage = l.try_into()?;
@@ -1140,6 +1792,22 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
 
             println!("Successfully downloaded bytecode");
         }
+        Commands::Logs { id, follow } => {
+            let request = tonic::Request::new(GetLogsRequest {
+                id: *id,
+                follow: *follow,
+            });
+            let mut stream = client.get_logs(request).await?.into_inner();
+
+            while let Some(record) = stream.next().await {
+                let LogRecord {
+                    level,
+                    target,
+                    message,
+                } = record?;
+                println!("{level} {target}: {message}");
+            }
+        }
     }
     Ok(())
 }
@@ -5,47 +5,270 @@ use std::{collections::HashMap, convert::TryInto};
 
 use anyhow::anyhow;
 use aya::{
+    maps::{loaded_maps, perf::AsyncPerfEventArray, MapData},
     programs::{
-        kprobe::KProbeLink, links::FdLink, loaded_programs, trace_point::TracePointLink,
-        uprobe::UProbeLink, KProbe, TracePoint, UProbe,
+        cgroup_skb::{CgroupSkbAttachType, CgroupSkbLink},
+        cgroup_sock::{CgroupSockAttachType, CgroupSockLink},
+        fentry::FEntryLink,
+        fexit::FExitLink,
+        kprobe::KProbeLink,
+        links::FdLink,
+        loaded_programs,
+        lsm::LsmLink,
+        perf_event::{PerfEventLink, PerfEventScope, PerfTypeId, SamplePolicy},
+        raw_trace_point::RawTracePointLink,
+        sock_ops::SockOpsLink,
+        trace_point::TracePointLink,
+        uprobe::UProbeLink,
+        CgroupSkb, CgroupSock, FEntry, FExit, KProbe, Lsm, PerfEvent, Program as LoadedProgram,
+        RawTracePoint, SchedClassifier, SockOps, TracePoint, UProbe,
     },
-    BpfLoader,
+    util::online_cpus,
+    BpfLoader, Btf, VerifierLogLevel,
 };
 use bpfd_api::{
     config::Config,
     util::directories::*,
+    v1::LogRecord,
     ProbeType::{self, *},
     ProgramType,
 };
+use bytes::BytesMut;
+use goblin::elf::{note::Note, Elf};
 use log::{debug, info};
-use tokio::{fs, select, sync::mpsc};
+use tokio::{
+    fs, select,
+    sync::{mpsc, oneshot},
+};
 use uuid::Uuid;
 
 use crate::{
     command::{
-        self, BpfMap, Command, Direction,
+        self, AttachCommandArgs, BpfMap, CgroupSkbProgram, CgroupSkbProgramInfo, CgroupSockProgram,
+        CgroupSockProgramInfo, Command, DetachArgs, Direction,
         Direction::{Egress, Ingress},
-        KprobeProgram, KprobeProgramInfo, LoadKprobeArgs, LoadTCArgs, LoadTracepointArgs,
-        LoadUprobeArgs, LoadXDPArgs, Program, ProgramData, ProgramInfo, PullBytecodeArgs,
-        TcProgram, TcProgramInfo, TracepointProgram, TracepointProgramInfo, UnloadArgs,
-        UprobeProgram, UprobeProgramInfo, XdpProgram, XdpProgramInfo,
+        FentryProgram, FentryProgramInfo, FexitProgram, FexitProgramInfo, KprobeProgram,
+        KprobeProgramInfo, LoadCgroupSkbArgs, LoadCgroupSockArgs, LoadFentryArgs, LoadFexitArgs,
+        LoadKprobeArgs, LoadLsmArgs, LoadPerfEventArgs, LoadRawTracepointArgs, LoadSockOpsArgs,
+        LoadTCArgs, LoadTracepointArgs, LoadUprobeArgs, LoadUsdtArgs, LoadXDPArgs, LogsArgs,
+        LsmProgram, LsmProgramInfo, PerfEventProgram, PerfEventProgramInfo, Program, ProgramData,
+        ProgramInfo, PullBytecodeArgs, RawTracepointProgram, RawTracepointProgramInfo,
+        SockOpsProgram, SockOpsProgramInfo, TcProgram, TcProgramInfo, TracepointProgram,
+        TracepointProgramInfo, UnloadArgs, UprobeProgram, UprobeProgramInfo, UsdtProgram,
+        UsdtProgramInfo, XdpProgram, XdpProgramInfo,
     },
     errors::BpfdError,
     multiprog::{Dispatcher, DispatcherId, DispatcherInfo, TcDispatcher, XdpDispatcher},
     oci_utils::image_manager::get_bytecode_from_image_store,
     serve::shutdown_handler,
-    utils::{get_ifindex, read, set_dir_permissions},
+    utils::{get_ifindex, read, read_to_string, set_dir_permissions},
 };
 
 const SUPERUSER: &str = "bpfctl";
 const MAPS_MODE: u32 = 0o0660;
 
+/// Identifies the kind of kernel link backing an attached program, so that
+/// `detach_program`/`attach_program` can't mix up link kinds across program
+/// types (e.g. re-attaching an XDP program's slot with kprobe args).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProgramLinkId {
+    Kprobe,
+    Uprobe,
+    Usdt,
+    Tracepoint,
+    PerfEvent,
+    Xdp,
+    Tc,
+    CgroupSkb,
+    CgroupSock,
+    SockOps,
+    Fentry,
+    Fexit,
+    RawTracepoint,
+    Lsm,
+}
+
+fn link_kind(program: &Program) -> ProgramLinkId {
+    match program {
+        Program::Xdp(_) => ProgramLinkId::Xdp,
+        Program::Tc(_) => ProgramLinkId::Tc,
+        Program::Tracepoint(_) => ProgramLinkId::Tracepoint,
+        Program::Kprobe(_) => ProgramLinkId::Kprobe,
+        Program::Uprobe(_) => ProgramLinkId::Uprobe,
+        Program::Usdt(_) => ProgramLinkId::Usdt,
+        Program::PerfEvent(_) => ProgramLinkId::PerfEvent,
+        Program::CgroupSkb(_) => ProgramLinkId::CgroupSkb,
+        Program::CgroupSock(_) => ProgramLinkId::CgroupSock,
+        Program::SockOps(_) => ProgramLinkId::SockOps,
+        Program::Fentry(_) => ProgramLinkId::Fentry,
+        Program::Fexit(_) => ProgramLinkId::Fexit,
+        Program::RawTracepoint(_) => ProgramLinkId::RawTracepoint,
+        Program::Lsm(_) => ProgramLinkId::Lsm,
+    }
+}
+
+/// Describes a single bytecode image to load as a batch: every loadable
+/// section in it is attached according to its section name, instead of the
+/// caller issuing one load RPC per section. `xdp_template`/`tc_template`
+/// supply the interface-scoped fields (`if_name`, `direction`, `proceed_on`,
+/// priority) that can't be inferred from the object itself -- any `xdp` or
+/// `classifier` section found without a matching template is an error.
+///
+/// `map_bind_name`, if set, makes the bundle its own map owner whose
+/// directory is pre-seeded with just that one named map -- the bundle
+/// shares exactly that map instead of inheriting an entire map set. The
+/// donor is `map_owner_uuid` if given, otherwise it's resolved by name via
+/// `resolve_shared_map_by_name`. Leave it unset to fall back to the
+/// existing whole-directory sharing behavior of `map_owner_uuid`.
+///
+/// `map_owner_name`, if set, resolves to the donor's `map_owner_uuid` via
+/// the by-name alias registry (`resolve_map_owner_name`) instead of the
+/// caller needing to already know it. `register_map_owner_name`, if set,
+/// registers this bundle's own map owner under that name once it's loaded
+/// (`name_map_owner`), so a later load can reference it the same way.
+pub(crate) struct LoadBundleArgs {
+    pub(crate) location: command::Location,
+    pub(crate) global_data: HashMap<String, Vec<u8>>,
+    pub(crate) map_owner_uuid: Option<Uuid>,
+    pub(crate) map_owner_name: Option<String>,
+    pub(crate) map_bind_name: Option<String>,
+    pub(crate) register_map_owner_name: Option<String>,
+    pub(crate) username: String,
+    pub(crate) xdp_template: Option<XdpProgramInfo>,
+    pub(crate) tc_template: Option<TcProgramInfo>,
+    pub(crate) responder: oneshot::Sender<Result<(Uuid, Vec<Uuid>), BpfdError>>,
+}
+
+/// Metadata for a single map, returned by `BpfManager::get_maps` so a
+/// caller can discover and open exactly the map it needs by name instead
+/// of assuming bpfd's `map_pin_path` directory layout or inheriting a
+/// program's entire map set.
+#[derive(Debug, Clone)]
+pub(crate) struct MapMetadata {
+    pub(crate) name: String,
+    pub(crate) map_type: u32,
+    pub(crate) key_size: u32,
+    pub(crate) value_size: u32,
+    pub(crate) max_entries: u32,
+    pub(crate) pin_path: String,
+    // BTF type IDs describing this map's key/value layout, carried over
+    // from the kernel's `bpf_map_info` the same way `map_type`/`key_size`
+    // are. A map loaded without BTF annotations (or on a kernel without
+    // BTF support) reports these as `0`, which is normalized to `None`.
+    pub(crate) btf_id: Option<u32>,
+    pub(crate) btf_key_type_id: Option<u32>,
+    pub(crate) btf_value_type_id: Option<u32>,
+}
+
+/// RAII guard over a map pin directory `manage_map_pin_path` just created.
+/// Removes the directory on `Drop` unless `commit()` ran first, so a load
+/// that fails, panics, or otherwise never reaches `save_map` can't leave an
+/// orphaned map directory behind on bpffs -- the prior code only cleaned up
+/// on the explicit `result.is_err()` branch in `add_program`, which a panic
+/// or an early `?` return added later would have bypassed.
+struct MapPinDirGuard {
+    path: String,
+    committed: bool,
+}
+
+impl MapPinDirGuard {
+    fn new(path: String) -> Self {
+        Self {
+            path,
+            committed: false,
+        }
+    }
+
+    /// The directory is now owned by `self.maps`; don't remove it on drop.
+    fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for MapPinDirGuard {
+    fn drop(&mut self) {
+        if !self.committed {
+            // Drop can't be async, so this falls back to the synchronous
+            // std::fs rather than the tokio::fs used everywhere else in
+            // this file.
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// Target parameters for re-attaching a loaded-but-detached program. Mirrors
+/// the per-type `*ProgramInfo`/`*Args` shapes used for the initial load, so a
+/// re-attach can target a new fn_name/offset/tracepoint or, for XDP/TC, a new
+/// interface entirely. Fentry/fexit programs and LSM programs are bound to
+/// their function or hook when loaded, so a re-attach naming a different
+/// one is rejected.
+pub(crate) enum AttachArgs {
+    Xdp(XdpProgramInfo),
+    Tc(TcProgramInfo),
+    Kprobe(KprobeProgramInfo),
+    Uprobe(UprobeProgramInfo),
+    Usdt(UsdtProgramInfo),
+    Tracepoint(TracepointProgramInfo),
+    PerfEvent(PerfEventProgramInfo),
+    CgroupSkb(CgroupSkbProgramInfo),
+    CgroupSock(CgroupSockProgramInfo),
+    SockOps(SockOpsProgramInfo),
+    Fentry(FentryProgramInfo),
+    Fexit(FexitProgramInfo),
+    RawTracepoint(RawTracepointProgramInfo),
+    Lsm(LsmProgramInfo),
+}
+
 pub(crate) struct BpfManager {
     config: Config,
     dispatchers: HashMap<DispatcherId, Dispatcher>,
     programs: HashMap<Uuid, Program>,
     maps: HashMap<Uuid, BpfMap>,
+    // Per-map metadata (name, type, sizes, pin path) for each map owner,
+    // keyed the same way as `maps`. Kept separate from `BpfMap` since it
+    // describes the individual maps in a directory rather than the
+    // directory's sharing state.
+    map_names: HashMap<Uuid, Vec<MapMetadata>>,
+    // Inner maps held by a `BPF_MAP_TYPE_ARRAY_OF_MAPS` /
+    // `BPF_MAP_TYPE_HASH_OF_MAPS` outer map, keyed by the outer map's
+    // index into `self.maps`. Each inner index is used as a key into
+    // `self.maps`, so `is_map_safe_to_delete`/`delete_map` can
+    // additionally refuse to remove an inner map while this table still
+    // lists it under a live outer map, and recurse into it once the outer
+    // map itself is gone. Recording an inner index (`record_inner_map`,
+    // `record_inner_maps`) creates no `BpfMap` entry for it; an inner map
+    // has one only if it was loaded as a map owner in its own right.
+    inner_maps: HashMap<Uuid, Vec<Uuid>>,
+    // External-name -> owner-UUID alias table for map owners, mirrored on
+    // bpffs as symlinks under `{RTDIR_FS_MAPS}/by-name` so a loading
+    // program can name a map it owns once and have later programs
+    // reference it by that name instead of the owner's generated UUID.
+    map_owner_names: HashMap<String, Uuid>,
+    // Tracks which loaded programs currently have a live kernel link.
+    // A program id present in `programs` but absent here is detached:
+    // its bytecode fd is still loaded and pinned, but it isn't running
+    // and, for XDP/TC, doesn't participate in the interface's dispatcher.
+    links: HashMap<Uuid, ProgramLinkId>,
+    // Groups the member UUIDs `add_program_bundle` loaded from one
+    // bytecode image under the bundle id it handed back to the caller, so
+    // `remove_program_bundle` can tear all of them down together instead
+    // of making the caller unload each member individually.
+    bundles: HashMap<Uuid, Vec<Uuid>>,
+    // The kernel-assigned bpf_link id backing each attached program's
+    // link, mirrored on bpffs next to the link pin itself so
+    // `rebuild_link_id` can recover it on restart. `rebuild_state` still
+    // marks every program found under RTDIR_PROGRAMS as attached, whether
+    // or not its link id is recovered.
+    link_ids: HashMap<Uuid, u32>,
     commands: mpsc::Receiver<Command>,
+    // The running kernel's BTF, loaded once on first use and reused for
+    // every later CO-RE relocation so a fleet running mixed kernel
+    // versions doesn't need a bytecode object rebuilt per host. `None`
+    // once a lookup has failed (no `/sys/kernel/btf/vmlinux`, e.g.
+    // CONFIG_DEBUG_INFO_BTF off); most loads and attaches then proceed
+    // without relocation instead of failing outright, while fentry, fexit
+    // and LSM loads, which need the kernel's BTF, fail.
+    btf: Option<Btf>,
 }
 
 impl BpfManager {
@@ -55,8 +278,31 @@ impl BpfManager {
             dispatchers: HashMap::new(),
             programs: HashMap::new(),
             maps: HashMap::new(),
+            map_names: HashMap::new(),
+            inner_maps: HashMap::new(),
+            map_owner_names: HashMap::new(),
+            links: HashMap::new(),
+            bundles: HashMap::new(),
+            link_ids: HashMap::new(),
             commands,
+            btf: None,
+        }
+    }
+
+    /// Returns the running kernel's BTF, loading and caching it from
+    /// `/sys/kernel/btf/vmlinux` on first call. Every loader and
+    /// BTF-based attach site should go through this rather than calling
+    /// `Btf::from_sys_fs()` directly, so CO-RE relocations and target
+    /// function lookups are performed against a single consistent
+    /// snapshot of the kernel's types for the life of the process.
+    fn system_btf(&mut self) -> Option<&Btf> {
+        if self.btf.is_none() {
+            match Btf::from_sys_fs() {
+                Ok(btf) => self.btf = Some(btf),
+                Err(e) => debug!("system BTF unavailable, loads will skip CO-RE relocation: {e}"),
+            }
         }
+        self.btf.as_ref()
     }
 
     pub(crate) async fn rebuild_state(&mut self) -> Result<(), anyhow::Error> {
@@ -70,8 +316,14 @@ impl BpfManager {
             program.set_attached();
             debug!("rebuilding state for program {}", uuid);
             self.rebuild_map_entry(uuid, program.data().map_owner_uuid);
+            let (_, map_index) = get_map_index(uuid, program.data().map_owner_uuid);
+            let (_, map_pin_path) = calc_map_pin_path(uuid, program.data().map_owner_uuid);
+            self.record_map_metadata(map_index, &map_pin_path);
+            self.links.insert(uuid, link_kind(&program));
+            self.rebuild_link_id(uuid).await;
             self.programs.insert(uuid, program);
         }
+        self.rebuild_map_owner_names().await;
         self.rebuild_dispatcher_state(ProgramType::Xdp, None, RTDIR_XDP_DISPATCHER)
             .await?;
         self.rebuild_dispatcher_state(ProgramType::Tc, Some(Ingress), RTDIR_TC_INGRESS_DISPATCHER)
@@ -131,6 +383,64 @@ impl BpfManager {
         Ok(())
     }
 
+    /// Pins a freshly-created link's fd under the default
+    /// `{RTDIR_FS}/prog_{id}_link` path and records its kernel-assigned
+    /// link id, the way `pin_and_record_link_at` does for callers (e.g.
+    /// per-cpu perf_event links) that need a non-default pin path.
+    async fn pin_and_record_link(&mut self, id: Uuid, fd_link: FdLink) -> Result<(), BpfdError> {
+        self.pin_and_record_link_at(id, format!("{RTDIR_FS}/prog_{id}_link"), fd_link)
+            .await
+    }
+
+    /// Pins `fd_link` at `pin_path` and, if the kernel reports a link id
+    /// for it, stores it in `self.link_ids` and mirrors it to
+    /// `{pin_path}_id` on bpffs so `rebuild_link_id` can recover it after
+    /// a restart. A program whose link doesn't expose an id (e.g. the
+    /// kernel doesn't support `bpf_link_get_info_by_fd` for its type)
+    /// still gets pinned as before; it's just not tracked by id.
+    async fn pin_and_record_link_at(
+        &mut self,
+        id: Uuid,
+        pin_path: String,
+        fd_link: FdLink,
+    ) -> Result<(), BpfdError> {
+        let link_id = fd_link.info().ok().map(|info| info.id());
+
+        fd_link
+            .pin(pin_path.clone())
+            .map_err(BpfdError::UnableToPinLink)?;
+
+        if let Some(link_id) = link_id {
+            self.link_ids.insert(id, link_id);
+            let _ = fs::write(format!("{pin_path}_id"), link_id.to_string()).await;
+        }
+
+        Ok(())
+    }
+
+    /// Recovers the kernel link id `pin_and_record_link` stashed for
+    /// `id` on a previous run by reading its `{RTDIR_FS}/prog_{id}_link_id`
+    /// sidecar file, populating `self.link_ids` so `rebuild_state` doesn't
+    /// have to rely solely on the in-memory bookkeeping that a restart
+    /// just lost. A missing or unreadable sidecar (e.g. an older pin
+    /// predating this file, or a link type that never got an id) just
+    /// leaves the program untracked by id -- it's still attached either
+    /// way.
+    async fn rebuild_link_id(&mut self, id: Uuid) {
+        if let Ok(contents) = read_to_string(format!("{RTDIR_FS}/prog_{id}_link_id")).await {
+            if let Ok(link_id) = contents.trim().parse() {
+                self.link_ids.insert(id, link_id);
+            }
+        }
+    }
+
+    /// The kernel-assigned bpf_link id for `id`'s attached link, if one
+    /// was captured at attach time. Lets callers report a stable link id
+    /// per program instead of only bpfd's own generated UUID.
+    pub(crate) fn link_id(&self, id: Uuid) -> Option<u32> {
+        self.link_ids.get(&id).copied()
+    }
+
     pub(crate) async fn add_program(
         &mut self,
         program: Program,
@@ -153,14 +463,26 @@ impl BpfManager {
         };
 
         let map_owner_uuid = program.data().map_owner_uuid;
-        let map_pin_path = self.manage_map_pin_path(uuid, map_owner_uuid).await?;
+        let (map_owner, map_pin_path) = self.manage_map_pin_path(uuid, map_owner_uuid).await?;
+        let pin_dir_guard = map_owner.then(|| MapPinDirGuard::new(map_pin_path.clone()));
 
         let result = match program {
             Program::Xdp(_) | Program::Tc(_) => {
                 self.add_multi_attach_program(program, uuid, map_pin_path.clone())
                     .await
             }
-            Program::Tracepoint(_) | Program::Kprobe(_) | Program::Uprobe(_) => {
+            Program::Tracepoint(_)
+            | Program::Kprobe(_)
+            | Program::Uprobe(_)
+            | Program::Usdt(_)
+            | Program::PerfEvent(_)
+            | Program::CgroupSkb(_)
+            | Program::CgroupSock(_)
+            | Program::SockOps(_)
+            | Program::Fentry(_)
+            | Program::Fexit(_)
+            | Program::RawTracepoint(_)
+            | Program::Lsm(_) => {
                 self.add_single_attach_program(program, uuid, map_pin_path.clone())
                     .await
             }
@@ -171,13 +493,200 @@ impl BpfManager {
             // and allow access to all maps by bpfd group members.
             self.save_map(uuid, map_owner_uuid, map_pin_path.clone())
                 .await?;
-        } else {
-            let _ = self.cleanup_map_pin_path(uuid, map_owner_uuid).await;
+            // self.maps now owns the directory; stop the guard from
+            // removing it when it goes out of scope below.
+            if let Some(guard) = pin_dir_guard {
+                guard.commit();
+            }
         }
+        // On failure, `pin_dir_guard` (if this program owned the
+        // directory) drops here and removes it.
 
         result
     }
 
+    /// Loads and attaches every recognized program section in `args`'s
+    /// bytecode image in one shot, inferring each section's program type
+    /// and attach target from its libbpf-style `SEC()` name, and returns
+    /// a bundle id alongside the member UUIDs bpfd assigned them in
+    /// section iteration order. The bundle id is recorded in
+    /// `self.bundles` so `remove_program_bundle` can unload every member
+    /// together without the caller tracking the individual UUIDs itself.
+    ///
+    /// Unless the bundle is given an existing map owner to share whole (a
+    /// map owner without `map_bind_name`), the first program loaded becomes
+    /// the map owner for the whole bundle: every section after it is loaded
+    /// with `map_owner_uuid` pointing back at it, so maps the object defines
+    /// are shared across the bundle without the caller wiring up
+    /// `map_owner_uuid` by hand. With such an owner, every section,
+    /// the first included, is loaded pointing at that owner instead.
+    /// If any section fails to load or attach, every program already
+    /// loaded from this bundle is torn back down and the triggering error
+    /// is returned.
+    pub(crate) async fn add_program_bundle(
+        &mut self,
+        args: &LoadBundleArgs,
+    ) -> Result<(Uuid, Vec<Uuid>), BpfdError> {
+        debug!("BpfManager::add_program_bundle()");
+
+        // `map_owner_name` is an alternative to `map_owner_uuid` for callers
+        // that don't know the donor's UUID -- resolved once up front via the
+        // by-name alias registry so the rest of this function only has to
+        // deal in UUIDs.
+        let resolved_map_owner_uuid = match (args.map_owner_uuid, &args.map_owner_name) {
+            (Some(id), _) => Some(id),
+            (None, Some(name)) => Some(self.resolve_map_owner_name(name)?),
+            (None, None) => None,
+        };
+
+        // Resolve the image/path once so the object bytes can be parsed for
+        // section introspection; each program below still gets its own
+        // ProgramData from the original location so persisted state (path,
+        // section_name) is correct for rebuild_state/list.
+        let probe_data = ProgramData::new(
+            args.location.clone(),
+            String::new(),
+            args.global_data.clone(),
+            resolved_map_owner_uuid,
+            args.username.clone(),
+        )
+        .await?;
+        let program_bytes = if probe_data.path.contains(BYTECODE_IMAGE_CONTENT_STORE) {
+            get_bytecode_from_image_store(probe_data.path.clone()).await?
+        } else {
+            read(probe_data.path.clone()).await?
+        };
+
+        let btf = self.system_btf().cloned();
+        let obj = BpfLoader::new()
+            .btf(btf.as_ref())
+            .load(&program_bytes)
+            .map_err(|e| BpfdError::Error(format!("unable to parse bytecode image: {e}")))?;
+
+        let mut loaded: Vec<Uuid> = Vec::new();
+        // When binding a single named map, the bundle is its own map
+        // owner (its directory is pre-seeded with just that one map
+        // below) rather than inheriting `map_owner_uuid`'s entire map
+        // set, so the effective owner starts out unset exactly as it
+        // would for a bundle with no `map_owner_uuid` at all.
+        let mut map_owner_uuid = if args.map_bind_name.is_some() {
+            None
+        } else {
+            resolved_map_owner_uuid
+        };
+
+        for (section_name, loaded_program) in obj.programs() {
+            let prog = match infer_bundle_program(
+                section_name,
+                loaded_program,
+                args.location.clone(),
+                args.global_data.clone(),
+                map_owner_uuid,
+                args.username.clone(),
+                args,
+            )
+            .await
+            {
+                Ok(Some(prog)) => prog,
+                Ok(None) => {
+                    debug!("skipping unsupported bundle section {section_name}");
+                    continue;
+                }
+                Err(e) => {
+                    for id in loaded {
+                        let _ = self.remove_program(id, args.username.clone()).await;
+                    }
+                    return Err(e);
+                }
+            };
+
+            // The first section that ends up the bundle's owner (i.e.
+            // `map_owner_uuid` hasn't been set to a bundle member yet) is
+            // the one whose directory gets pre-seeded with the donor's
+            // named map; every later section just inherits it normally.
+            let forced_id = match (&args.map_bind_name, map_owner_uuid) {
+                (Some(map_name), None) => {
+                    let id = Uuid::new_v4();
+                    let bound = match resolved_map_owner_uuid {
+                        Some(donor) => self.bind_shared_map(id, donor, map_name).await,
+                        None => self.bind_shared_map_by_name(id, map_name).await,
+                    }
+                    .map(|_| id);
+                    match bound {
+                        Ok(id) => Some(id),
+                        Err(e) => {
+                            for id in loaded {
+                                let _ = self.remove_program(id, args.username.clone()).await;
+                            }
+                            return Err(e);
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            match self.add_program(prog, forced_id).await {
+                Ok(id) => {
+                    if map_owner_uuid.is_none() {
+                        map_owner_uuid = Some(id);
+                    }
+                    loaded.push(id);
+                }
+                Err(e) => {
+                    for id in loaded {
+                        let _ = self.remove_program(id, args.username.clone()).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        if loaded.is_empty() {
+            return Err(BpfdError::Error(
+                "bytecode image contains no recognized program sections".to_string(),
+            ));
+        }
+
+        if let Some(name) = &args.register_map_owner_name {
+            // map_owner_uuid is always set by now -- either resolved up
+            // front or, for a fresh owner, assigned to the first loaded
+            // section's id above.
+            self.name_map_owner(name.clone(), map_owner_uuid.unwrap())
+                .await?;
+        }
+
+        let bundle_id = Uuid::new_v4();
+        self.bundles.insert(bundle_id, loaded.clone());
+
+        Ok((bundle_id, loaded))
+    }
+
+    /// Tears down every program `add_program_bundle` loaded under
+    /// `bundle_id` as one group. Mirrors `remove_program`'s ownership
+    /// check per member; if any member fails to unload (e.g. its map is
+    /// still referenced elsewhere), the rest of the group is still torn
+    /// down and the first error encountered is returned.
+    pub(crate) async fn remove_program_bundle(
+        &mut self,
+        bundle_id: Uuid,
+        owner: String,
+    ) -> Result<(), BpfdError> {
+        debug!("BpfManager::remove_program_bundle() bundle_id: {bundle_id}");
+        let members = self.bundles.remove(&bundle_id).ok_or(BpfdError::InvalidID)?;
+
+        let mut first_err = None;
+        for id in members {
+            if let Err(e) = self.remove_program(id, owner.clone()).await {
+                first_err.get_or_insert(e);
+            }
+        }
+
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
     pub(crate) async fn add_multi_attach_program(
         &mut self,
         program: Program,
@@ -199,10 +708,35 @@ impl BpfManager {
 
         // This load is just to verify the Section Name is valid.
         // The actual load is performed in the XDP or TC logic.
-        let mut ext_loader = BpfLoader::new()
+        let btf = self.system_btf().cloned();
+        let mut ext_loader = BpfLoader::new();
+        ext_loader
             .extension(&program.data().section_name)
-            .map_pin_path(map_pin_path.clone())
-            .load(&program_bytes)?;
+            .btf(btf.as_ref())
+            .map_pin_path(map_pin_path.clone());
+
+        // The kernel only emits a useful rejection reason when the verifier
+        // log is enabled, so retry a failed load with full verbosity rather
+        // than paying the log overhead on every successful load. aya grows
+        // its log buffer and retries internally when the kernel reports
+        // ENOSPC, so by the time this returns the text is as complete as
+        // the kernel will give us.
+        let mut ext_loader = match ext_loader
+            .verifier_log_level(VerifierLogLevel::STATS)
+            .load(&program_bytes)
+        {
+            Ok(loaded) => loaded,
+            Err(_) => match ext_loader
+                .verifier_log_level(VerifierLogLevel::VERBOSE)
+                .load(&program_bytes)
+            {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    let log = e.to_string();
+                    return Err(BpfdError::VerifierError { id, log });
+                }
+            },
+        };
 
         match ext_loader.program_mut(&program.data().section_name) {
             Some(_) => Ok(()),
@@ -215,8 +749,8 @@ impl BpfManager {
         let next_available_id = self
             .programs
             .iter()
-            .filter(|(_, p)| {
-                if p.kind() == program.kind() {
+            .filter(|(k, p)| {
+                if p.kind() == program.kind() && self.links.contains_key(*k) {
                     p.if_index() == program.if_index() && p.direction() == program.direction()
                 } else {
                     false
@@ -238,8 +772,10 @@ impl BpfManager {
         let did = program
             .dispatcher_id()
             .ok_or(BpfdError::DispatcherNotRequired)?;
+        let kind = link_kind(&program);
 
         self.programs.insert(id, program);
+        self.links.insert(id, kind);
         self.sort_programs(program_type, if_index, direction);
         let mut programs = self.collect_programs(program_type, if_index, direction);
         let old_dispatcher = self.dispatchers.remove(&did);
@@ -256,6 +792,7 @@ impl BpfManager {
         let dispatcher = Dispatcher::new(if_config, &mut programs, next_revision, old_dispatcher)
             .await
             .or_else(|e| {
+                self.links.remove(&id);
                 let prog = self.programs.remove(&id).unwrap();
                 prog.delete(id).map_err(|_| {
                     BpfdError::Error(
@@ -295,15 +832,37 @@ impl BpfManager {
             read(p.data().path.clone()).await?
         };
 
+        let btf = self.system_btf().cloned();
         let mut loader = BpfLoader::new();
+        loader.btf(btf.as_ref());
 
         for (name, value) in &p.data().global_data {
             loader.set_global(name, value.as_slice(), true);
         }
-
-        let mut loader = loader
-            .map_pin_path(map_pin_path.clone())
-            .load(&program_bytes)?;
+        loader.map_pin_path(map_pin_path.clone());
+
+        // The kernel only emits a useful rejection reason when the verifier
+        // log is enabled, so retry a failed load with full verbosity rather
+        // than paying the log overhead on every successful load. aya grows
+        // its log buffer and retries internally when the kernel reports
+        // ENOSPC, so by the time this returns the text is as complete as
+        // the kernel will give us.
+        let mut loader = match loader
+            .verifier_log_level(VerifierLogLevel::STATS)
+            .load(&program_bytes)
+        {
+            Ok(loaded) => loaded,
+            Err(_) => match loader
+                .verifier_log_level(VerifierLogLevel::VERBOSE)
+                .load(&program_bytes)
+            {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    let log = e.to_string();
+                    return Err(BpfdError::VerifierError { id, log });
+                }
+            },
+        };
 
         let raw_program =
             loader
@@ -345,9 +904,7 @@ impl BpfManager {
                 let fd_link: FdLink = owned_link
                     .try_into()
                     .expect("unable to get owned tracepoint attach link");
-                fd_link
-                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
-                    .map_err(BpfdError::UnableToPinLink)?;
+                self.pin_and_record_link(id, fd_link).await?;
 
                 tracepoint
                     .pin(format!("{RTDIR_FS}/prog_{id}"))
@@ -362,6 +919,7 @@ impl BpfManager {
                         Err(BpfdError::UnableToPinProgram(e))
                     })?;
 
+                self.links.insert(id, ProgramLinkId::Tracepoint);
                 Ok(id)
             }
             Program::Kprobe(program) => {
@@ -410,9 +968,7 @@ impl BpfManager {
                 let fd_link: FdLink = owned_link
                     .try_into()
                     .expect("unable to get owned kprobe attach link");
-                fd_link
-                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
-                    .map_err(BpfdError::UnableToPinLink)?;
+                self.pin_and_record_link(id, fd_link).await?;
 
                 kprobe.pin(format!("{RTDIR_FS}/prog_{id}")).or_else(|e| {
                     let prog = self.programs.remove(&id).unwrap();
@@ -424,6 +980,7 @@ impl BpfManager {
                     Err(BpfdError::UnableToPinProgram(e))
                 })?;
 
+                self.links.insert(id, ProgramLinkId::Kprobe);
                 Ok(id)
             }
             Program::Uprobe(ref program) => {
@@ -448,11 +1005,14 @@ impl BpfManager {
                 p.save(id)
                     .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
 
+                let target =
+                    resolve_namespaced_target(&program.info.target, &program.info.namespace)?;
+
                 let link_id = uprobe
                     .attach(
                         program.info.fn_name.as_deref(),
                         program.info.offset,
-                        program.info.target.clone(),
+                        target,
                         program.info.pid,
                     )
                     .or_else(|e| {
@@ -471,9 +1031,7 @@ impl BpfManager {
                 let fd_link: FdLink = owned_link
                     .try_into()
                     .expect("unable to get owned uprobe attach link");
-                fd_link
-                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
-                    .map_err(BpfdError::UnableToPinLink)?;
+                self.pin_and_record_link(id, fd_link).await?;
 
                 uprobe.pin(format!("{RTDIR_FS}/prog_{id}")).or_else(|e| {
                     let prog = self.programs.remove(&id).unwrap();
@@ -485,109 +1043,1065 @@ impl BpfManager {
                     Err(BpfdError::UnableToPinProgram(e))
                 })?;
 
+                self.links.insert(id, ProgramLinkId::Uprobe);
                 Ok(id)
             }
-            _ => panic!("not a supported single attach program"),
-        }
-    }
+            Program::Usdt(ref program) => {
+                let target =
+                    resolve_namespaced_target(&program.info.target, &program.info.namespace)?;
+                let probe =
+                    resolve_usdt_probe(&target, &program.info.provider, &program.info.probe)?;
 
-    pub(crate) async fn remove_program(
-        &mut self,
-        id: Uuid,
-        owner: String,
-    ) -> Result<(), BpfdError> {
-        debug!("BpfManager::remove_program() id: {id}");
-        if let Some(prog) = self.programs.get(&id) {
-            if !(prog.owner() == &owner || owner == SUPERUSER) {
-                return Err(BpfdError::NotAuthorized);
-            }
-            if !self.is_map_safe_to_delete(id, prog.data().map_owner_uuid) {
-                return Err(BpfdError::Error(
-                    "map being used by other eBPF program".to_string(),
-                ));
-            }
-        } else {
-            debug!("InvalidID: {id}");
-            return Err(BpfdError::InvalidID);
-        }
+                let uprobe: &mut UProbe = raw_program.try_into()?;
+                uprobe.load()?;
 
-        let prog = self.programs.remove(&id).unwrap();
+                p.set_kernel_info(uprobe.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
 
-        let map_owner_uuid = prog.data().map_owner_uuid;
+                let link_id = uprobe
+                    .attach_with_ref_ctr_offset(
+                        None,
+                        probe.offset,
+                        target,
+                        program.info.pid,
+                        probe.ref_ctr_offset,
+                    )
+                    .or_else(|e| {
+                        p.delete(id).map_err(|_| {
+                            BpfdError::Error(
+                                "new program cleanup failed, unable to delete program data"
+                                    .to_string(),
+                            )
+                        })?;
+                        Err(BpfdError::BpfProgramError(e))
+                    })?;
 
-        prog.delete(id)
-            .map_err(|_| BpfdError::Error("unable to delete program data".to_string()))?;
+                self.programs.insert(id, p);
 
-        match prog {
-            Program::Xdp(_) | Program::Tc(_) => self.remove_multi_attach_program(prog).await?,
-            Program::Tracepoint(_) | Program::Kprobe(_) | Program::Uprobe(_) => (),
-        }
+                let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned usdt attach link");
+                self.pin_and_record_link(id, fd_link).await?;
 
-        self.delete_map(id, map_owner_uuid).await?;
-        Ok(())
-    }
+                uprobe.pin(format!("{RTDIR_FS}/prog_{id}")).or_else(|e| {
+                    let prog = self.programs.remove(&id).unwrap();
+                    prog.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::UnableToPinProgram(e))
+                })?;
 
-    pub(crate) async fn remove_multi_attach_program(
-        &mut self,
-        program: Program,
-    ) -> Result<(), BpfdError> {
-        debug!("BpfManager::remove_multi_attach_program()");
-        // Calculate the next_available_id
-        let next_available_id = self
-            .programs
-            .iter()
-            .filter(|(_, p)| {
-                if p.kind() == program.kind() {
-                    p.if_index() == program.if_index() && p.direction() == program.direction()
-                } else {
-                    false
+                self.links.insert(id, ProgramLinkId::Usdt);
+                Ok(id)
+            }
+            Program::PerfEvent(ref program) => {
+                let perf_event: &mut PerfEvent = raw_program.try_into()?;
+                perf_event.load()?;
+
+                p.set_kernel_info(perf_event.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
+
+                let sample_policy =
+                    match (program.info.sample_period, program.info.sample_frequency) {
+                        (Some(period), _) => SamplePolicy::Period(period),
+                        (None, Some(freq)) => SamplePolicy::Frequency(freq),
+                        (None, None) => SamplePolicy::Period(1),
+                    };
+
+                let cpus: Vec<i32> = match program.info.cpu {
+                    Some(cpu) => vec![cpu],
+                    None => online_cpus()
+                        .map_err(|e| {
+                            BpfdError::Error(format!("unable to enumerate online cpus: {e:?}"))
+                        })?
+                        .into_iter()
+                        .map(|c| c as i32)
+                        .collect(),
+                };
+
+                let mut link_ids = Vec::new();
+                for cpu in cpus {
+                    let scope = match program.info.pid {
+                        Some(pid) => PerfEventScope::OneProcessOneCpu {
+                            cpu: cpu as u32,
+                            pid: pid as u32,
+                        },
+                        None => PerfEventScope::AllProcessesOneCpu { cpu: cpu as u32 },
+                    };
+
+                    let link_id = perf_event
+                        .attach(
+                            PerfTypeId::from(program.info.perf_type),
+                            program.info.config,
+                            scope,
+                            sample_policy,
+                        )
+                        .or_else(|e| {
+                            p.delete(id).map_err(|_| {
+                                BpfdError::Error(
+                                    "new program cleanup failed, unable to delete program data"
+                                        .to_string(),
+                                )
+                            })?;
+                            Err(BpfdError::BpfProgramError(e))
+                        })?;
+                    link_ids.push((cpu, link_id));
                 }
-            })
-            .collect::<HashMap<_, _>>()
-            .len();
-        debug!("next_available_id = {next_available_id}");
 
-        let did = program
-            .dispatcher_id()
-            .ok_or(BpfdError::DispatcherNotRequired)?;
+                self.programs.insert(id, p);
 
-        let mut old_dispatcher = self.dispatchers.remove(&did);
+                for (cpu, link_id) in link_ids {
+                    let owned_link: PerfEventLink = perf_event.take_link(link_id)?;
+                    let fd_link: FdLink = owned_link
+                        .try_into()
+                        .expect("unable to get owned perf event attach link");
+                    self.pin_and_record_link_at(id, format!("{RTDIR_FS}/prog_{id}_link_{cpu}"), fd_link).await?;
+                }
 
-        if let Some(ref mut old) = old_dispatcher {
-            if next_available_id == 0 {
-                // Delete the dispatcher
-                return old.delete(true);
+                perf_event
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .or_else(|e| {
+                        let prog = self.programs.remove(&id).unwrap();
+                        prog.delete(id).map_err(|_| {
+                            BpfdError::Error(
+                                "new program cleanup failed, unable to delete program data"
+                                    .to_string(),
+                            )
+                        })?;
+                        Err(BpfdError::UnableToPinProgram(e))
+                    })?;
+
+                self.links.insert(id, ProgramLinkId::PerfEvent);
+                Ok(id)
             }
-        }
+            Program::CgroupSkb(ref program) => {
+                let cgroup_file = std::fs::File::open(&program.info.cgroup).map_err(|e| {
+                    BpfdError::Error(format!(
+                        "unable to open cgroup {}: {e}",
+                        program.info.cgroup
+                    ))
+                })?;
+                let attach_type = cgroup_skb_attach_type(program.info.direction);
 
-        let program_type = program.kind();
-        let if_index = program.if_index();
-        let if_name = program.if_name().unwrap();
-        let direction = program.direction();
+                let cgroup_skb: &mut CgroupSkb = raw_program.try_into()?;
+                cgroup_skb.load()?;
 
-        self.sort_programs(program_type, if_index, direction);
+                p.set_kernel_info(cgroup_skb.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
 
-        let mut programs = self.collect_programs(program_type, if_index, direction);
+                let link_id = cgroup_skb.attach(cgroup_file, attach_type).or_else(|e| {
+                    p.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::BpfProgramError(e))
+                })?;
 
-        let if_config = if let Some(ref i) = self.config.interfaces {
-            i.get(&if_name)
-        } else {
-            None
-        };
-        let next_revision = if let Some(ref old) = old_dispatcher {
-            old.next_revision()
-        } else {
-            1
-        };
-        debug!("next_revision = {next_revision}");
-        let dispatcher =
-            Dispatcher::new(if_config, &mut programs, next_revision, old_dispatcher).await?;
-        self.dispatchers.insert(did, dispatcher);
-        Ok(())
-    }
+                self.programs.insert(id, p);
 
-    pub(crate) async fn rebuild_multiattach_dispatcher(
-        &mut self,
+                let owned_link: CgroupSkbLink = cgroup_skb.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned cgroup_skb attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+
+                cgroup_skb
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .or_else(|e| {
+                        let prog = self.programs.remove(&id).unwrap();
+                        prog.delete(id).map_err(|_| {
+                            BpfdError::Error(
+                                "new program cleanup failed, unable to delete program data"
+                                    .to_string(),
+                            )
+                        })?;
+                        Err(BpfdError::UnableToPinProgram(e))
+                    })?;
+
+                self.links.insert(id, ProgramLinkId::CgroupSkb);
+                Ok(id)
+            }
+            Program::CgroupSock(ref program) => {
+                let cgroup_file = std::fs::File::open(&program.info.cgroup).map_err(|e| {
+                    BpfdError::Error(format!(
+                        "unable to open cgroup {}: {e}",
+                        program.info.cgroup
+                    ))
+                })?;
+                let attach_type = parse_cgroup_sock_attach_type(&program.info.attach_type)?;
+
+                let cgroup_sock: &mut CgroupSock = raw_program.try_into()?;
+                cgroup_sock.load()?;
+
+                p.set_kernel_info(cgroup_sock.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
+
+                let link_id = cgroup_sock.attach(cgroup_file, attach_type).or_else(|e| {
+                    p.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::BpfProgramError(e))
+                })?;
+
+                self.programs.insert(id, p);
+
+                let owned_link: CgroupSockLink = cgroup_sock.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned cgroup_sock attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+
+                cgroup_sock
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .or_else(|e| {
+                        let prog = self.programs.remove(&id).unwrap();
+                        prog.delete(id).map_err(|_| {
+                            BpfdError::Error(
+                                "new program cleanup failed, unable to delete program data"
+                                    .to_string(),
+                            )
+                        })?;
+                        Err(BpfdError::UnableToPinProgram(e))
+                    })?;
+
+                self.links.insert(id, ProgramLinkId::CgroupSock);
+                Ok(id)
+            }
+            Program::SockOps(ref program) => {
+                let cgroup_file = std::fs::File::open(&program.info.cgroup).map_err(|e| {
+                    BpfdError::Error(format!(
+                        "unable to open cgroup {}: {e}",
+                        program.info.cgroup
+                    ))
+                })?;
+
+                let sock_ops: &mut SockOps = raw_program.try_into()?;
+                sock_ops.load()?;
+
+                p.set_kernel_info(sock_ops.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
+
+                let link_id = sock_ops.attach(cgroup_file).or_else(|e| {
+                    p.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::BpfProgramError(e))
+                })?;
+
+                self.programs.insert(id, p);
+
+                let owned_link: SockOpsLink = sock_ops.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned sock_ops attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+
+                sock_ops.pin(format!("{RTDIR_FS}/prog_{id}")).or_else(|e| {
+                    let prog = self.programs.remove(&id).unwrap();
+                    prog.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::UnableToPinProgram(e))
+                })?;
+
+                self.links.insert(id, ProgramLinkId::SockOps);
+                Ok(id)
+            }
+            Program::Fentry(ref program) => {
+                let btf = self
+                    .system_btf()
+                    .cloned()
+                    .ok_or_else(|| BpfdError::Error("system BTF unavailable".to_string()))?;
+
+                let fentry: &mut FEntry = raw_program.try_into()?;
+                fentry.load(&program.info.fn_name, &btf)?;
+
+                p.set_kernel_info(fentry.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
+
+                let link_id = fentry.attach().or_else(|e| {
+                    p.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::BpfProgramError(e))
+                })?;
+
+                self.programs.insert(id, p);
+
+                let owned_link: FEntryLink = fentry.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned fentry attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+
+                fentry.pin(format!("{RTDIR_FS}/prog_{id}")).or_else(|e| {
+                    let prog = self.programs.remove(&id).unwrap();
+                    prog.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::UnableToPinProgram(e))
+                })?;
+
+                self.links.insert(id, ProgramLinkId::Fentry);
+                Ok(id)
+            }
+            Program::Fexit(ref program) => {
+                let btf = self
+                    .system_btf()
+                    .cloned()
+                    .ok_or_else(|| BpfdError::Error("system BTF unavailable".to_string()))?;
+
+                let fexit: &mut FExit = raw_program.try_into()?;
+                fexit.load(&program.info.fn_name, &btf)?;
+
+                p.set_kernel_info(fexit.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
+
+                let link_id = fexit.attach().or_else(|e| {
+                    p.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::BpfProgramError(e))
+                })?;
+
+                self.programs.insert(id, p);
+
+                let owned_link: FExitLink = fexit.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned fexit attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+
+                fexit.pin(format!("{RTDIR_FS}/prog_{id}")).or_else(|e| {
+                    let prog = self.programs.remove(&id).unwrap();
+                    prog.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::UnableToPinProgram(e))
+                })?;
+
+                self.links.insert(id, ProgramLinkId::Fexit);
+                Ok(id)
+            }
+            Program::RawTracepoint(ref program) => {
+                let raw_tracepoint: &mut RawTracePoint = raw_program.try_into()?;
+                raw_tracepoint.load()?;
+
+                p.set_kernel_info(raw_tracepoint.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
+
+                let link_id = raw_tracepoint
+                    .attach(program.info.tp_name.as_str())
+                    .or_else(|e| {
+                        p.delete(id).map_err(|_| {
+                            BpfdError::Error(
+                                "new program cleanup failed, unable to delete program data"
+                                    .to_string(),
+                            )
+                        })?;
+                        Err(BpfdError::BpfProgramError(e))
+                    })?;
+
+                self.programs.insert(id, p);
+
+                let owned_link: RawTracePointLink = raw_tracepoint.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned raw_tracepoint attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+
+                raw_tracepoint
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .or_else(|e| {
+                        let prog = self.programs.remove(&id).unwrap();
+                        prog.delete(id).map_err(|_| {
+                            BpfdError::Error(
+                                "new program cleanup failed, unable to delete program data"
+                                    .to_string(),
+                            )
+                        })?;
+                        Err(BpfdError::UnableToPinProgram(e))
+                    })?;
+
+                self.links.insert(id, ProgramLinkId::RawTracepoint);
+                Ok(id)
+            }
+            Program::Lsm(ref program) => {
+                let btf = self
+                    .system_btf()
+                    .cloned()
+                    .ok_or_else(|| BpfdError::Error("system BTF unavailable".to_string()))?;
+
+                let lsm: &mut Lsm = raw_program.try_into()?;
+                lsm.load(&program.info.hook, &btf)?;
+
+                p.set_kernel_info(lsm.program_info()?.try_into()?);
+                p.save(id)
+                    .map_err(|_| BpfdError::Error("unable to persist program data".to_string()))?;
+
+                let link_id = lsm.attach().or_else(|e| {
+                    p.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::BpfProgramError(e))
+                })?;
+
+                self.programs.insert(id, p);
+
+                let owned_link: LsmLink = lsm.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned lsm attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+
+                lsm.pin(format!("{RTDIR_FS}/prog_{id}")).or_else(|e| {
+                    let prog = self.programs.remove(&id).unwrap();
+                    prog.delete(id).map_err(|_| {
+                        BpfdError::Error(
+                            "new program cleanup failed, unable to delete program data".to_string(),
+                        )
+                    })?;
+                    Err(BpfdError::UnableToPinProgram(e))
+                })?;
+
+                self.links.insert(id, ProgramLinkId::Lsm);
+                Ok(id)
+            }
+            _ => panic!("not a supported single attach program"),
+        }
+    }
+
+    /// Drops/unpins a loaded program's link so it stops running, without
+    /// unloading it: the program fd stays pinned at `prog_{id}` and its
+    /// `ProgramData` stays in `self.programs`, so a later `attach_program`
+    /// can relink it without paying verifier/load cost again. For XDP/TC
+    /// this also rebuilds the interface's dispatcher to drop the program
+    /// from the chain.
+    pub(crate) async fn detach_program(
+        &mut self,
+        id: Uuid,
+        owner: String,
+    ) -> Result<(), BpfdError> {
+        debug!("BpfManager::detach_program() id: {id}");
+        match self.programs.get(&id) {
+            Some(prog) => {
+                if !(prog.owner() == &owner || owner == SUPERUSER) {
+                    return Err(BpfdError::NotAuthorized);
+                }
+            }
+            None => {
+                debug!("InvalidID: {id}");
+                return Err(BpfdError::InvalidID);
+            }
+        }
+
+        let kind = self.links.remove(&id).ok_or(BpfdError::NotAttached)?;
+        self.link_ids.remove(&id);
+
+        match kind {
+            ProgramLinkId::Xdp | ProgramLinkId::Tc => self.detach_multi_attach_program(id).await,
+            ProgramLinkId::PerfEvent => self.detach_perf_event_program(id).await,
+            ProgramLinkId::Kprobe
+            | ProgramLinkId::Uprobe
+            | ProgramLinkId::Usdt
+            | ProgramLinkId::Tracepoint
+            | ProgramLinkId::CgroupSkb
+            | ProgramLinkId::CgroupSock
+            | ProgramLinkId::SockOps
+            | ProgramLinkId::Fentry
+            | ProgramLinkId::Fexit
+            | ProgramLinkId::RawTracepoint
+            | ProgramLinkId::Lsm => {
+                let _ = fs::remove_file(format!("{RTDIR_FS}/prog_{id}_link")).await;
+                let _ = fs::remove_file(format!("{RTDIR_FS}/prog_{id}_link_id")).await;
+                Ok(())
+            }
+        }
+    }
+
+    async fn detach_perf_event_program(&mut self, id: Uuid) -> Result<(), BpfdError> {
+        let cpus: Vec<i32> = match self.programs.get(&id) {
+            Some(Program::PerfEvent(p)) => match p.info.cpu {
+                Some(cpu) => vec![cpu],
+                None => online_cpus()
+                    .map_err(|e| {
+                        BpfdError::Error(format!("unable to enumerate online cpus: {e:?}"))
+                    })?
+                    .into_iter()
+                    .map(|c| c as i32)
+                    .collect(),
+            },
+            _ => return Err(BpfdError::InvalidID),
+        };
+        for cpu in cpus {
+            let _ = fs::remove_file(format!("{RTDIR_FS}/prog_{id}_link_{cpu}")).await;
+            let _ = fs::remove_file(format!("{RTDIR_FS}/prog_{id}_link_{cpu}_id")).await;
+        }
+        Ok(())
+    }
+
+    async fn detach_multi_attach_program(&mut self, id: Uuid) -> Result<(), BpfdError> {
+        debug!("BpfManager::detach_multi_attach_program() id: {id}");
+        let program = self.programs.get(&id).unwrap().clone();
+        let program_type = program.kind();
+        let if_index = program.if_index();
+        let if_name = program.if_name().unwrap();
+        let direction = program.direction();
+
+        let did = program
+            .dispatcher_id()
+            .ok_or(BpfdError::DispatcherNotRequired)?;
+
+        let mut old_dispatcher = self.dispatchers.remove(&did);
+
+        // The link for `id` was already removed from self.links by the
+        // caller, so this only counts programs still in the chain.
+        let remaining = self
+            .programs
+            .iter()
+            .filter(|(k, p)| {
+                p.kind() == program_type
+                    && p.if_index() == if_index
+                    && p.direction() == direction
+                    && self.links.contains_key(*k)
+            })
+            .count();
+
+        if remaining == 0 {
+            if let Some(ref mut old) = old_dispatcher {
+                return old.delete(true);
+            }
+            return Ok(());
+        }
+
+        self.sort_programs(program_type, if_index, direction);
+        let mut programs = self.collect_programs(program_type, if_index, direction);
+
+        let if_config = if let Some(ref i) = self.config.interfaces {
+            i.get(&if_name)
+        } else {
+            None
+        };
+        let next_revision = if let Some(ref old) = old_dispatcher {
+            old.next_revision()
+        } else {
+            1
+        };
+        let dispatcher =
+            Dispatcher::new(if_config, &mut programs, next_revision, old_dispatcher).await?;
+        self.dispatchers.insert(did, dispatcher);
+
+        programs.iter().for_each(|(i, p)| {
+            self.programs.insert(i.to_owned(), p.to_owned());
+        });
+
+        Ok(())
+    }
+
+    /// Re-attaches a previously `detach_program`'d program, possibly to a
+    /// new target. The program fd is reopened from its bpffs pin rather
+    /// than reloaded, so this doesn't touch the verifier.
+    pub(crate) async fn attach_program(
+        &mut self,
+        id: Uuid,
+        owner: String,
+        attach_args: AttachArgs,
+    ) -> Result<(), BpfdError> {
+        debug!("BpfManager::attach_program() id: {id}");
+        match self.programs.get(&id) {
+            Some(prog) => {
+                if !(prog.owner() == &owner || owner == SUPERUSER) {
+                    return Err(BpfdError::NotAuthorized);
+                }
+            }
+            None => {
+                debug!("InvalidID: {id}");
+                return Err(BpfdError::InvalidID);
+            }
+        }
+        if self.links.contains_key(&id) {
+            return Err(BpfdError::AlreadyAttached);
+        }
+
+        let mut p = self.programs.remove(&id).unwrap();
+
+        // Wrapped in a closure so a `?` or early `return` inside any arm
+        // below short-circuits just this match, not `attach_program` itself
+        // -- `p` must always make it back into `self.programs` afterwards.
+        let result: Result<(), BpfdError> = (|| match (&mut p, attach_args) {
+            (Program::Xdp(program), AttachArgs::Xdp(info)) => match get_ifindex(&info.if_name) {
+                Ok(if_index) => {
+                    program.info = XdpProgramInfo {
+                        if_index,
+                        current_position: None,
+                        ..info
+                    };
+                    Ok(())
+                }
+                Err(_) => Err(BpfdError::InvalidInterface),
+            },
+            (Program::Tc(program), AttachArgs::Tc(info)) => match get_ifindex(&info.if_name) {
+                Ok(if_index) => {
+                    program.info = TcProgramInfo {
+                        if_index,
+                        current_position: None,
+                        ..info
+                    };
+                    Ok(())
+                }
+                Err(_) => Err(BpfdError::InvalidInterface),
+            },
+            (Program::Kprobe(program), AttachArgs::Kprobe(info)) => {
+                let mut kprobe =
+                    KProbe::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let link_id = kprobe
+                    .attach(info.fn_name.as_str(), info.offset)
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned kprobe attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::Uprobe(program), AttachArgs::Uprobe(info)) => {
+                let mut uprobe =
+                    UProbe::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let target = resolve_namespaced_target(&info.target, &info.namespace)?;
+                let link_id = uprobe
+                    .attach(info.fn_name.as_deref(), info.offset, target, info.pid)
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned uprobe attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::Usdt(program), AttachArgs::Usdt(info)) => {
+                let target = resolve_namespaced_target(&info.target, &info.namespace)?;
+                let probe = resolve_usdt_probe(&target, &info.provider, &info.probe)?;
+                let mut uprobe =
+                    UProbe::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let link_id = uprobe
+                    .attach_with_ref_ctr_offset(
+                        None,
+                        probe.offset,
+                        target,
+                        info.pid,
+                        probe.ref_ctr_offset,
+                    )
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned usdt attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::Tracepoint(program), AttachArgs::Tracepoint(info)) => {
+                let parts: Vec<&str> = info.tracepoint.split('/').collect();
+                if parts.len() != 2 {
+                    return Err(BpfdError::InvalidAttach(info.tracepoint.to_string()));
+                }
+                let mut tracepoint = TracePoint::from_pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let link_id = tracepoint
+                    .attach(parts[0], parts[1])
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: TracePointLink = tracepoint.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned tracepoint attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::PerfEvent(program), AttachArgs::PerfEvent(info)) => {
+                let mut perf_event =
+                    PerfEvent::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+
+                let sample_policy = match (info.sample_period, info.sample_frequency) {
+                    (Some(period), _) => SamplePolicy::Period(period),
+                    (None, Some(freq)) => SamplePolicy::Frequency(freq),
+                    (None, None) => SamplePolicy::Period(1),
+                };
+                let cpus: Vec<i32> = match info.cpu {
+                    Some(cpu) => vec![cpu],
+                    None => online_cpus()
+                        .map_err(|e| {
+                            BpfdError::Error(format!("unable to enumerate online cpus: {e:?}"))
+                        })?
+                        .into_iter()
+                        .map(|c| c as i32)
+                        .collect(),
+                };
+                let mut link_ids = Vec::new();
+                for cpu in &cpus {
+                    let scope = match info.pid {
+                        Some(pid) => PerfEventScope::OneProcessOneCpu {
+                            cpu: *cpu as u32,
+                            pid: pid as u32,
+                        },
+                        None => PerfEventScope::AllProcessesOneCpu { cpu: *cpu as u32 },
+                    };
+                    let link_id = perf_event
+                        .attach(
+                            PerfTypeId::from(info.perf_type),
+                            info.config,
+                            scope,
+                            sample_policy,
+                        )
+                        .map_err(BpfdError::BpfProgramError)?;
+                    link_ids.push((*cpu, link_id));
+                }
+                for (cpu, link_id) in link_ids {
+                    let owned_link: PerfEventLink = perf_event.take_link(link_id)?;
+                    let fd_link: FdLink = owned_link
+                        .try_into()
+                        .expect("unable to get owned perf event attach link");
+                    self.pin_and_record_link_at(id, format!("{RTDIR_FS}/prog_{id}_link_{cpu}"), fd_link).await?;
+                }
+                program.info = info;
+                Ok(())
+            }
+            (Program::CgroupSkb(program), AttachArgs::CgroupSkb(info)) => {
+                let cgroup_file = std::fs::File::open(&info.cgroup).map_err(|e| {
+                    BpfdError::Error(format!("unable to open cgroup {}: {e}", info.cgroup))
+                })?;
+                let mut cgroup_skb =
+                    CgroupSkb::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let link_id = cgroup_skb
+                    .attach(cgroup_file, cgroup_skb_attach_type(info.direction))
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: CgroupSkbLink = cgroup_skb.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned cgroup_skb attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::CgroupSock(program), AttachArgs::CgroupSock(info)) => {
+                let cgroup_file = std::fs::File::open(&info.cgroup).map_err(|e| {
+                    BpfdError::Error(format!("unable to open cgroup {}: {e}", info.cgroup))
+                })?;
+                let attach_type = parse_cgroup_sock_attach_type(&info.attach_type)?;
+                let mut cgroup_sock = CgroupSock::from_pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let link_id = cgroup_sock
+                    .attach(cgroup_file, attach_type)
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: CgroupSockLink = cgroup_sock.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned cgroup_sock attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::SockOps(program), AttachArgs::SockOps(info)) => {
+                let cgroup_file = std::fs::File::open(&info.cgroup).map_err(|e| {
+                    BpfdError::Error(format!("unable to open cgroup {}: {e}", info.cgroup))
+                })?;
+                let mut sock_ops =
+                    SockOps::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let link_id = sock_ops
+                    .attach(cgroup_file)
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: SockOpsLink = sock_ops.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned sock_ops attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::Fentry(program), AttachArgs::Fentry(info)) => {
+                if info.fn_name != program.info.fn_name {
+                    return Err(BpfdError::Error(format!(
+                        "fentry program is bound to {} at load time",
+                        program.info.fn_name
+                    )));
+                }
+                let mut fentry =
+                    FEntry::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                        BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                    })?;
+                let link_id = fentry.attach().map_err(BpfdError::BpfProgramError)?;
+                let owned_link: FEntryLink = fentry.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned fentry attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::Fexit(program), AttachArgs::Fexit(info)) => {
+                if info.fn_name != program.info.fn_name {
+                    return Err(BpfdError::Error(format!(
+                        "fexit program is bound to {} at load time",
+                        program.info.fn_name
+                    )));
+                }
+                let mut fexit = FExit::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                    BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                })?;
+                let link_id = fexit.attach().map_err(BpfdError::BpfProgramError)?;
+                let owned_link: FExitLink = fexit.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned fexit attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::RawTracepoint(program), AttachArgs::RawTracepoint(info)) => {
+                let mut raw_tracepoint = RawTracePoint::from_pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(|e| {
+                    BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                })?;
+                let link_id = raw_tracepoint
+                    .attach(info.tp_name.as_str())
+                    .map_err(BpfdError::BpfProgramError)?;
+                let owned_link: RawTracePointLink = raw_tracepoint.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned raw_tracepoint attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            (Program::Lsm(program), AttachArgs::Lsm(info)) => {
+                if info.hook != program.info.hook {
+                    return Err(BpfdError::Error(format!(
+                        "lsm program is bound to {} at load time",
+                        program.info.hook
+                    )));
+                }
+                let mut lsm = Lsm::from_pin(format!("{RTDIR_FS}/prog_{id}")).map_err(|e| {
+                    BpfdError::Error(format!("unable to re-open pinned program: {e}"))
+                })?;
+                let link_id = lsm.attach().map_err(BpfdError::BpfProgramError)?;
+                let owned_link: LsmLink = lsm.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned lsm attach link");
+                self.pin_and_record_link(id, fd_link).await?;
+                program.info = info;
+                Ok(())
+            }
+            _ => Err(BpfdError::Error(
+                "attach target does not match the loaded program's type".to_string(),
+            )),
+        })();
+
+        let kind = link_kind(&p);
+        self.programs.insert(id, p);
+
+        if result.is_ok() {
+            self.links.insert(id, kind);
+            if kind == ProgramLinkId::Xdp || kind == ProgramLinkId::Tc {
+                if let Err(e) = self.attach_multi_attach_program(id).await {
+                    self.links.remove(&id);
+                    return Err(e);
+                }
+            } else if let Some(p) = self.programs.get_mut(&id) {
+                p.save(id)
+                    .map_err(|e| BpfdError::Error(format!("unable to save program state: {e}")))?;
+            }
+        }
+
+        result
+    }
+
+    async fn attach_multi_attach_program(&mut self, id: Uuid) -> Result<(), BpfdError> {
+        debug!("BpfManager::attach_multi_attach_program() id: {id}");
+        let program = self.programs.get(&id).unwrap().clone();
+        let program_type = program.kind();
+        let if_index = program.if_index();
+        let if_name = program.if_name().unwrap();
+        let direction = program.direction();
+
+        let did = program
+            .dispatcher_id()
+            .ok_or(BpfdError::DispatcherNotRequired)?;
+
+        self.sort_programs(program_type, if_index, direction);
+        let mut programs = self.collect_programs(program_type, if_index, direction);
+        let old_dispatcher = self.dispatchers.remove(&did);
+        let if_config = if let Some(ref i) = self.config.interfaces {
+            i.get(&if_name)
+        } else {
+            None
+        };
+        let next_revision = if let Some(ref old) = old_dispatcher {
+            old.next_revision()
+        } else {
+            1
+        };
+        let dispatcher =
+            Dispatcher::new(if_config, &mut programs, next_revision, old_dispatcher).await?;
+        self.dispatchers.insert(did, dispatcher);
+
+        programs.iter().for_each(|(i, p)| {
+            self.programs.insert(i.to_owned(), p.to_owned());
+        });
+        if let Some(p) = self.programs.get_mut(&id) {
+            p.save(id)
+                .map_err(|e| BpfdError::Error(format!("unable to save program state: {e}")))?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn remove_program(
+        &mut self,
+        id: Uuid,
+        owner: String,
+    ) -> Result<(), BpfdError> {
+        debug!("BpfManager::remove_program() id: {id}");
+        if let Some(prog) = self.programs.get(&id) {
+            if !(prog.owner() == &owner || owner == SUPERUSER) {
+                return Err(BpfdError::NotAuthorized);
+            }
+            if !self.is_map_safe_to_delete(id, prog.data().map_owner_uuid) {
+                let (_, map_index) = get_map_index(id, prog.data().map_owner_uuid);
+                return Err(map_in_use(map_index));
+            }
+        } else {
+            debug!("InvalidID: {id}");
+            return Err(BpfdError::InvalidID);
+        }
+
+        let prog = self.programs.remove(&id).unwrap();
+        let was_attached = self.links.remove(&id).is_some();
+        self.link_ids.remove(&id);
+
+        let map_owner_uuid = prog.data().map_owner_uuid;
+
+        prog.delete(id)
+            .map_err(|_| BpfdError::Error("unable to delete program data".to_string()))?;
+
+        match prog {
+            Program::Xdp(_) | Program::Tc(_) if was_attached => {
+                self.remove_multi_attach_program(prog).await?
+            }
+            _ => (),
+        }
+
+        self.delete_map(id, map_owner_uuid).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn remove_multi_attach_program(
+        &mut self,
+        program: Program,
+    ) -> Result<(), BpfdError> {
+        debug!("BpfManager::remove_multi_attach_program()");
+        // Calculate the next_available_id
+        let next_available_id = self
+            .programs
+            .iter()
+            .filter(|(k, p)| {
+                if p.kind() == program.kind() && self.links.contains_key(*k) {
+                    p.if_index() == program.if_index() && p.direction() == program.direction()
+                } else {
+                    false
+                }
+            })
+            .collect::<HashMap<_, _>>()
+            .len();
+        debug!("next_available_id = {next_available_id}");
+
+        let did = program
+            .dispatcher_id()
+            .ok_or(BpfdError::DispatcherNotRequired)?;
+
+        let mut old_dispatcher = self.dispatchers.remove(&did);
+
+        if let Some(ref mut old) = old_dispatcher {
+            if next_available_id == 0 {
+                // Delete the dispatcher
+                return old.delete(true);
+            }
+        }
+
+        let program_type = program.kind();
+        let if_index = program.if_index();
+        let if_name = program.if_name().unwrap();
+        let direction = program.direction();
+
+        self.sort_programs(program_type, if_index, direction);
+
+        let mut programs = self.collect_programs(program_type, if_index, direction);
+
+        let if_config = if let Some(ref i) = self.config.interfaces {
+            i.get(&if_name)
+        } else {
+            None
+        };
+        let next_revision = if let Some(ref old) = old_dispatcher {
+            old.next_revision()
+        } else {
+            1
+        };
+        debug!("next_revision = {next_revision}");
+        let dispatcher =
+            Dispatcher::new(if_config, &mut programs, next_revision, old_dispatcher).await?;
+        self.dispatchers.insert(did, dispatcher);
+        Ok(())
+    }
+
+    pub(crate) async fn rebuild_multiattach_dispatcher(
+        &mut self,
         program_type: ProgramType,
         if_index: u32,
         direction: Option<Direction>,
@@ -678,13 +2192,227 @@ impl BpfManager {
                             kernel_info,
                         },
                     ),
-                    Program::Tracepoint(p) => (
+                    Program::Tracepoint(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::Tracepoint as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::Tracepoint(
+                                crate::command::TracepointAttachInfo {
+                                    tracepoint: p.info.tracepoint.to_string(),
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::Tc(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::Tc as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::Tc(
+                                crate::command::TcAttachInfo {
+                                    iface: p.info.if_name.to_string(),
+                                    priority: p.info.metadata.priority,
+                                    proceed_on: p.info.proceed_on.clone(),
+                                    direction: p.info.direction,
+                                    position: p.info.current_position.unwrap_or_default() as i32,
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::Kprobe(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::Probe as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::Kprobe(
+                                crate::command::KprobeAttachInfo {
+                                    fn_name: p.info.fn_name.clone(),
+                                    offset: p.info.offset,
+                                    retprobe: p.info.retprobe,
+                                    namespace: p.info.namespace.clone(),
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::Uprobe(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::Probe as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::Uprobe(
+                                crate::command::UprobeAttachInfo {
+                                    fn_name: p.info.fn_name.clone(),
+                                    offset: p.info.offset,
+                                    target: p.info.target.clone(),
+                                    retprobe: p.info.retprobe,
+                                    pid: p.info.pid,
+                                    namespace: p.info.namespace.clone(),
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::CgroupSkb(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::CgroupSkb as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::CgroupSkb(
+                                crate::command::CgroupSkbAttachInfo {
+                                    cgroup: p.info.cgroup.clone(),
+                                    direction: p.info.direction,
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::CgroupSock(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::CgroupSock as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::CgroupSock(
+                                crate::command::CgroupSockAttachInfo {
+                                    cgroup: p.info.cgroup.clone(),
+                                    attach_type: p.info.attach_type.clone(),
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::SockOps(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::SockOps as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::SockOps(
+                                crate::command::SockOpsAttachInfo {
+                                    cgroup: p.info.cgroup.clone(),
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::Fentry(p) => (
+                        prog_id,
+                        ProgramInfo {
+                            id: Some(*id),
+                            name: Some(p.data.section_name.to_string()),
+                            location,
+                            program_type: Some(ProgramType::Fentry as u32),
+                            global_data: Some(p.data.global_data.clone()),
+                            map_owner_uuid: p.data.map_owner_uuid,
+                            map_pin_path: Some(
+                                self.get_map_pin_path(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            map_used_by: Some(
+                                self.get_map_used_by(*id, p.data.map_owner_uuid)
+                                    .unwrap_or_default(),
+                            ),
+                            attach_info: Some(crate::command::AttachInfo::Fentry(
+                                crate::command::FentryAttachInfo {
+                                    fn_name: p.info.fn_name.clone(),
+                                },
+                            )),
+                            kernel_info,
+                        },
+                    ),
+                    Program::Fexit(p) => (
                         prog_id,
                         ProgramInfo {
                             id: Some(*id),
                             name: Some(p.data.section_name.to_string()),
                             location,
-                            program_type: Some(ProgramType::Tracepoint as u32),
+                            program_type: Some(ProgramType::Fexit as u32),
                             global_data: Some(p.data.global_data.clone()),
                             map_owner_uuid: p.data.map_owner_uuid,
                             map_pin_path: Some(
@@ -695,21 +2423,21 @@ impl BpfManager {
                                 self.get_map_used_by(*id, p.data.map_owner_uuid)
                                     .unwrap_or_default(),
                             ),
-                            attach_info: Some(crate::command::AttachInfo::Tracepoint(
-                                crate::command::TracepointAttachInfo {
-                                    tracepoint: p.info.tracepoint.to_string(),
+                            attach_info: Some(crate::command::AttachInfo::Fexit(
+                                crate::command::FexitAttachInfo {
+                                    fn_name: p.info.fn_name.clone(),
                                 },
                             )),
                             kernel_info,
                         },
                     ),
-                    Program::Tc(p) => (
+                    Program::RawTracepoint(p) => (
                         prog_id,
                         ProgramInfo {
                             id: Some(*id),
                             name: Some(p.data.section_name.to_string()),
                             location,
-                            program_type: Some(ProgramType::Tc as u32),
+                            program_type: Some(ProgramType::RawTracepoint as u32),
                             global_data: Some(p.data.global_data.clone()),
                             map_owner_uuid: p.data.map_owner_uuid,
                             map_pin_path: Some(
@@ -720,25 +2448,21 @@ impl BpfManager {
                                 self.get_map_used_by(*id, p.data.map_owner_uuid)
                                     .unwrap_or_default(),
                             ),
-                            attach_info: Some(crate::command::AttachInfo::Tc(
-                                crate::command::TcAttachInfo {
-                                    iface: p.info.if_name.to_string(),
-                                    priority: p.info.metadata.priority,
-                                    proceed_on: p.info.proceed_on.clone(),
-                                    direction: p.info.direction,
-                                    position: p.info.current_position.unwrap_or_default() as i32,
+                            attach_info: Some(crate::command::AttachInfo::RawTracepoint(
+                                crate::command::RawTracepointAttachInfo {
+                                    tp_name: p.info.tp_name.clone(),
                                 },
                             )),
                             kernel_info,
                         },
                     ),
-                    Program::Kprobe(p) => (
+                    Program::Lsm(p) => (
                         prog_id,
                         ProgramInfo {
                             id: Some(*id),
                             name: Some(p.data.section_name.to_string()),
                             location,
-                            program_type: Some(ProgramType::Probe as u32),
+                            program_type: Some(ProgramType::Lsm as u32),
                             global_data: Some(p.data.global_data.clone()),
                             map_owner_uuid: p.data.map_owner_uuid,
                             map_pin_path: Some(
@@ -749,24 +2473,21 @@ impl BpfManager {
                                 self.get_map_used_by(*id, p.data.map_owner_uuid)
                                     .unwrap_or_default(),
                             ),
-                            attach_info: Some(crate::command::AttachInfo::Kprobe(
-                                crate::command::KprobeAttachInfo {
-                                    fn_name: p.info.fn_name.clone(),
-                                    offset: p.info.offset,
-                                    retprobe: p.info.retprobe,
-                                    namespace: p.info.namespace.clone(),
+                            attach_info: Some(crate::command::AttachInfo::Lsm(
+                                crate::command::LsmAttachInfo {
+                                    hook: p.info.hook.clone(),
                                 },
                             )),
                             kernel_info,
                         },
                     ),
-                    Program::Uprobe(p) => (
+                    Program::PerfEvent(p) => (
                         prog_id,
                         ProgramInfo {
                             id: Some(*id),
                             name: Some(p.data.section_name.to_string()),
                             location,
-                            program_type: Some(ProgramType::Probe as u32),
+                            program_type: Some(ProgramType::PerfEvent as u32),
                             global_data: Some(p.data.global_data.clone()),
                             map_owner_uuid: p.data.map_owner_uuid,
                             map_pin_path: Some(
@@ -777,14 +2498,14 @@ impl BpfManager {
                                 self.get_map_used_by(*id, p.data.map_owner_uuid)
                                     .unwrap_or_default(),
                             ),
-                            attach_info: Some(crate::command::AttachInfo::Uprobe(
-                                crate::command::UprobeAttachInfo {
-                                    fn_name: p.info.fn_name.clone(),
-                                    offset: p.info.offset,
-                                    target: p.info.target.clone(),
-                                    retprobe: p.info.retprobe,
+                            attach_info: Some(crate::command::AttachInfo::PerfEvent(
+                                crate::command::PerfEventAttachInfo {
+                                    perf_type: p.info.perf_type,
+                                    config: p.info.config,
+                                    sample_period: p.info.sample_period,
+                                    sample_frequency: p.info.sample_frequency,
                                     pid: p.info.pid,
-                                    namespace: p.info.namespace.clone(),
+                                    cpu: p.info.cpu,
                                 },
                             )),
                             kernel_info,
@@ -824,11 +2545,12 @@ impl BpfManager {
         if_index: Option<u32>,
         direction: Option<Direction>,
     ) {
+        let links = &self.links;
         let mut extensions = self
             .programs
             .iter_mut()
             .filter_map(|(k, v)| {
-                if v.kind() == program_type {
+                if v.kind() == program_type && links.contains_key(k) {
                     if v.if_index() == if_index && v.direction() == direction {
                         Some((k, v))
                     } else {
@@ -853,7 +2575,11 @@ impl BpfManager {
     ) -> Vec<(Uuid, Program)> {
         let mut results = vec![];
         for (k, v) in self.programs.iter() {
-            if v.kind() == program_type && v.if_index() == if_index && v.direction() == direction {
+            if v.kind() == program_type
+                && v.if_index() == if_index
+                && v.direction() == direction
+                && self.links.contains_key(k)
+            {
                 results.push((k.to_owned(), v.clone()))
             }
         }
@@ -889,13 +2615,25 @@ impl BpfManager {
                         Command::LoadTracepoint(args) => self.load_tracepoint_command(args).await.unwrap(),
                         Command::LoadKprobe(args) => self.load_kprobe_command(args).await.unwrap(),
                         Command::LoadUprobe(args) => self.load_uprobe_command(args).await.unwrap(),
+                        Command::LoadCgroupSkb(args) => self.load_cgroup_skb_command(args).await.unwrap(),
+                        Command::LoadCgroupSock(args) => self.load_cgroup_sock_command(args).await.unwrap(),
+                        Command::LoadSockOps(args) => self.load_sock_ops_command(args).await.unwrap(),
+                        Command::LoadFentry(args) => self.load_fentry_command(args).await.unwrap(),
+                        Command::LoadFexit(args) => self.load_fexit_command(args).await.unwrap(),
+                        Command::LoadRawTracepoint(args) => self.load_raw_tracepoint_command(args).await.unwrap(),
+                        Command::LoadLsm(args) => self.load_lsm_command(args).await.unwrap(),
+                        Command::LoadPerfEvent(args) => self.load_perf_event_command(args).await.unwrap(),
                         Command::Unload(args) => self.unload_command(args).await.unwrap(),
+                        Command::Detach(args) => self.detach_command(args).await.unwrap(),
+                        Command::Attach(args) => self.attach_command(args).await.unwrap(),
+                        Command::LoadBundle(args) => self.load_bundle_command(args).await.unwrap(),
                         Command::List { responder } => {
                             let progs = self.list_programs();
                             // Ignore errors as they'll be propagated to caller in the RPC status
                             let _ = responder.send(progs);
                         }
                         Command::PullBytecode (args) => self.pull_bytecode(args).await.unwrap(),
+                        Command::Logs(args) => self.logs_command(args).await.unwrap(),
                     }
                 }
             }
@@ -975,8 +2713,256 @@ impl BpfManager {
                 }
                 Err(e) => Err(e),
             }
-        } else {
-            Err(BpfdError::InvalidInterface)
+        } else {
+            Err(BpfdError::InvalidInterface)
+        };
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_tracepoint_command(&mut self, args: LoadTracepointArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::Tracepoint(TracepointProgram {
+                        data: prog_data,
+                        info: TracepointProgramInfo {
+                            tracepoint: args.tracepoint,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_kprobe_command(&mut self, args: LoadKprobeArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::Kprobe(KprobeProgram {
+                        data: prog_data,
+                        info: KprobeProgramInfo {
+                            fn_name: args.fn_name,
+                            offset: args.offset,
+                            retprobe: args.retprobe,
+                            namespace: args._namespace,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        // If program was successfully loaded, allow map access by bpfd group members.
+        if let Ok(uuid) = &res {
+            let maps_dir = format!("{RTDIR_FS_MAPS}/{uuid}");
+            set_dir_permissions(&maps_dir, MAPS_MODE).await;
+        }
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_uprobe_command(&mut self, args: LoadUprobeArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::Uprobe(UprobeProgram {
+                        data: prog_data,
+                        info: UprobeProgramInfo {
+                            fn_name: args.fn_name,
+                            offset: args.offset,
+                            target: args.target,
+                            retprobe: args.retprobe,
+                            pid: args.pid,
+                            namespace: args._namespace,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_cgroup_skb_command(&mut self, args: LoadCgroupSkbArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::CgroupSkb(CgroupSkbProgram {
+                        data: prog_data,
+                        info: CgroupSkbProgramInfo {
+                            cgroup: args.cgroup,
+                            direction: args.direction,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_cgroup_sock_command(&mut self, args: LoadCgroupSockArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::CgroupSock(CgroupSockProgram {
+                        data: prog_data,
+                        info: CgroupSockProgramInfo {
+                            cgroup: args.cgroup,
+                            attach_type: args.attach_type,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_sock_ops_command(&mut self, args: LoadSockOpsArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::SockOps(SockOpsProgram {
+                        data: prog_data,
+                        info: SockOpsProgramInfo {
+                            cgroup: args.cgroup,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_fentry_command(&mut self, args: LoadFentryArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::Fentry(FentryProgram {
+                        data: prog_data,
+                        info: FentryProgramInfo {
+                            fn_name: args.fn_name,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_fexit_command(&mut self, args: LoadFexitArgs) -> anyhow::Result<()> {
+        let res = {
+            match ProgramData::new(
+                args.location,
+                args.section_name,
+                args.global_data,
+                args.map_owner_uuid,
+                args.username,
+            )
+            .await
+            {
+                Ok(prog_data) => {
+                    let prog = Program::Fexit(FexitProgram {
+                        data: prog_data,
+                        info: FexitProgramInfo {
+                            fn_name: args.fn_name,
+                        },
+                    });
+                    self.add_program(prog, args.id).await
+                }
+                Err(e) => Err(e),
+            }
         };
 
         // Ignore errors as they'll be propagated to caller in the RPC status
@@ -984,7 +2970,10 @@ impl BpfManager {
         Ok(())
     }
 
-    async fn load_tracepoint_command(&mut self, args: LoadTracepointArgs) -> anyhow::Result<()> {
+    async fn load_raw_tracepoint_command(
+        &mut self,
+        args: LoadRawTracepointArgs,
+    ) -> anyhow::Result<()> {
         let res = {
             match ProgramData::new(
                 args.location,
@@ -996,10 +2985,10 @@ impl BpfManager {
             .await
             {
                 Ok(prog_data) => {
-                    let prog = Program::Tracepoint(TracepointProgram {
+                    let prog = Program::RawTracepoint(RawTracepointProgram {
                         data: prog_data,
-                        info: TracepointProgramInfo {
-                            tracepoint: args.tracepoint,
+                        info: RawTracepointProgramInfo {
+                            tp_name: args.tp_name,
                         },
                     });
                     self.add_program(prog, args.id).await
@@ -1013,7 +3002,7 @@ impl BpfManager {
         Ok(())
     }
 
-    async fn load_kprobe_command(&mut self, args: LoadKprobeArgs) -> anyhow::Result<()> {
+    async fn load_lsm_command(&mut self, args: LoadLsmArgs) -> anyhow::Result<()> {
         let res = {
             match ProgramData::new(
                 args.location,
@@ -1025,14 +3014,9 @@ impl BpfManager {
             .await
             {
                 Ok(prog_data) => {
-                    let prog = Program::Kprobe(KprobeProgram {
+                    let prog = Program::Lsm(LsmProgram {
                         data: prog_data,
-                        info: KprobeProgramInfo {
-                            fn_name: args.fn_name,
-                            offset: args.offset,
-                            retprobe: args.retprobe,
-                            namespace: args._namespace,
-                        },
+                        info: LsmProgramInfo { hook: args.hook },
                     });
                     self.add_program(prog, args.id).await
                 }
@@ -1040,18 +3024,12 @@ impl BpfManager {
             }
         };
 
-        // If program was successfully loaded, allow map access by bpfd group members.
-        if let Ok(uuid) = &res {
-            let maps_dir = format!("{RTDIR_FS_MAPS}/{uuid}");
-            set_dir_permissions(&maps_dir, MAPS_MODE).await;
-        }
-
         // Ignore errors as they'll be propagated to caller in the RPC status
         let _ = args.responder.send(res);
         Ok(())
     }
 
-    async fn load_uprobe_command(&mut self, args: LoadUprobeArgs) -> anyhow::Result<()> {
+    async fn load_perf_event_command(&mut self, args: LoadPerfEventArgs) -> anyhow::Result<()> {
         let res = {
             match ProgramData::new(
                 args.location,
@@ -1063,15 +3041,15 @@ impl BpfManager {
             .await
             {
                 Ok(prog_data) => {
-                    let prog = Program::Uprobe(UprobeProgram {
+                    let prog = Program::PerfEvent(PerfEventProgram {
                         data: prog_data,
-                        info: UprobeProgramInfo {
-                            fn_name: args.fn_name,
-                            offset: args.offset,
-                            target: args.target,
-                            retprobe: args.retprobe,
+                        info: PerfEventProgramInfo {
+                            perf_type: args.perf_type,
+                            config: args.config,
+                            sample_period: args.sample_period,
+                            sample_frequency: args.sample_frequency,
                             pid: args.pid,
-                            namespace: args._namespace,
+                            cpu: args.cpu,
                         },
                     });
                     self.add_program(prog, args.id).await
@@ -1092,6 +3070,29 @@ impl BpfManager {
         Ok(())
     }
 
+    async fn detach_command(&mut self, args: DetachArgs) -> anyhow::Result<()> {
+        let res = self.detach_program(args.id, args.username).await;
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn attach_command(&mut self, args: AttachCommandArgs) -> anyhow::Result<()> {
+        let res = self
+            .attach_program(args.id, args.username, args.attach_args)
+            .await;
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn load_bundle_command(&mut self, args: LoadBundleArgs) -> anyhow::Result<()> {
+        let res = self.add_program_bundle(&args).await;
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
     // This function reads the map_pin_path from the map hash table. If there
     // is not an entry for the given input, an error is returned.
     fn get_map_pin_path(
@@ -1104,7 +3105,7 @@ impl BpfManager {
         if let Some(map) = self.maps.get(&map_index) {
             Ok(map.map_pin_path.clone())
         } else {
-            Err(BpfdError::Error("map does not exists".to_string()))
+            Err(map_not_found(map_index))
         }
     }
 
@@ -1131,17 +3132,121 @@ impl BpfManager {
             }
             Ok(used_by)
         } else {
-            Err(BpfdError::Error("map does not exists".to_string()))
+            Err(map_not_found(map_index))
+        }
+    }
+
+    /// Finds the bpfd-internal UUID of the program the kernel assigned
+    /// `kernel_id` to, the same lookup `list_programs` does implicitly via
+    /// `kernel_info.id` but exposed for callers (like `logs_command`) that
+    /// only have the kernel id a client gave them.
+    fn program_by_kernel_id(&self, kernel_id: u32) -> Option<(Uuid, &Program)> {
+        self.programs.iter().find_map(|(id, p)| {
+            let matches = p
+                .data()
+                .kernel_info
+                .as_ref()
+                .is_some_and(|k| k.id == kernel_id);
+            matches.then_some((*id, p))
+        })
+    }
+
+    async fn logs_command(&mut self, args: LogsArgs) -> anyhow::Result<()> {
+        let res = self.open_program_logs(args.id, args.follow).await;
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    /// Finds `kernel_id`'s `AYA_LOGS` perf event array -- the map
+    /// aya-log-backed programs write framed log records to -- by its pin
+    /// path, and spawns one reader task per CPU decoding each sample
+    /// (`decode_log_record`) into a `LogRecord` sent over the returned
+    /// channel. With `follow` false, each reader stops once no new sample
+    /// arrives within 100ms instead of waiting on new records, so the
+    /// stream ends once whatever was already buffered is drained.
+    async fn open_program_logs(
+        &self,
+        kernel_id: u32,
+        follow: bool,
+    ) -> Result<mpsc::Receiver<LogRecord>, BpfdError> {
+        let (id, program) = self
+            .program_by_kernel_id(kernel_id)
+            .ok_or(BpfdError::InvalidID)?;
+        let map_owner_uuid = program.data().map_owner_uuid;
+
+        let map_pin_path = self.get_map_pin_path(id, map_owner_uuid)?;
+        let logs_path = format!("{map_pin_path}/AYA_LOGS");
+
+        let map_data = MapData::from_pin(&logs_path)
+            .map_err(|e| BpfdError::Error(format!("unable to open AYA_LOGS map: {e}")))?;
+        let mut perf_array: AsyncPerfEventArray<MapData> = map_data
+            .try_into()
+            .map_err(|e| BpfdError::Error(format!("AYA_LOGS is not a perf event array: {e}")))?;
+
+        let (tx, rx) = mpsc::channel(1024);
+
+        for cpu_id in online_cpus()
+            .map_err(|e| BpfdError::Error(format!("unable to enumerate online cpus: {e:?}")))?
+        {
+            let mut buf = perf_array
+                .open(cpu_id, None)
+                .map_err(|e| BpfdError::Error(format!("unable to open AYA_LOGS buffer: {e}")))?;
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let mut bufs = (0..10)
+                    .map(|_| BytesMut::with_capacity(4096))
+                    .collect::<Vec<_>>();
+                loop {
+                    let read = if follow {
+                        buf.read_events(&mut bufs).await
+                    } else {
+                        // `read_events` only returns once a sample or a loss
+                        // is available, so an idle buffer shows up as a timeout.
+                        match tokio::time::timeout(
+                            std::time::Duration::from_millis(100),
+                            buf.read_events(&mut bufs),
+                        )
+                        .await
+                        {
+                            Ok(read) => read,
+                            Err(_) => break,
+                        }
+                    };
+                    let events = match read {
+                        Ok(events) => events,
+                        Err(_) => break,
+                    };
+                    if events.read == 0 {
+                        if !follow {
+                            break;
+                        }
+                        continue;
+                    }
+                    for sample in bufs.iter().take(events.read) {
+                        if let Some(record) = decode_log_record(sample) {
+                            if tx.send(record).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            });
         }
+
+        Ok(rx)
     }
 
-    // This function returns the map_pin_path, and if this eBPF program is
-    // the map owner, creates the directory to store the associate maps.
+    // This function returns whether this eBPF program is the map owner
+    // along with the map_pin_path, and if it is the owner, creates the
+    // directory to store the associated maps. The caller is expected to
+    // wrap a freshly-created directory in a `MapPinDirGuard` so it's
+    // cleaned back up if the program never makes it to `save_map`.
     async fn manage_map_pin_path(
         &mut self,
         id: Uuid,
         map_owner_uuid: Option<Uuid>,
-    ) -> Result<String, BpfdError> {
+    ) -> Result<(bool, String), BpfdError> {
         let (map_owner, map_pin_path) = calc_map_pin_path(id, map_owner_uuid);
 
         // If the user provided a UUID of an eBPF program to share a map with,
@@ -1151,42 +3256,15 @@ impl BpfManager {
         if map_owner {
             fs::create_dir_all(map_pin_path.clone())
                 .await
-                .map_err(|e| BpfdError::Error(format!("can't create map dir: {e}")))?;
+                .map_err(|e| map_syscall_error("create_dir_all", e))?;
 
-            // Return the map_pin_path
-            Ok(map_pin_path)
+            Ok((map_owner, map_pin_path))
         } else {
-            if self.maps.contains_key(&map_owner_uuid.unwrap()) {
-                // Return the map_pin_path
-                return Ok(map_pin_path);
+            let owner_uuid = map_owner_uuid.unwrap();
+            if self.maps.contains_key(&owner_uuid) {
+                return Ok((map_owner, map_pin_path));
             }
-            Err(BpfdError::Error(
-                "map_owner_uuid does not exists".to_string(),
-            ))
-        }
-    }
-
-    // This function is called if manage_map_pin_path() was already called,
-    // but the eBPF program failed to load. save_map() has not been called,
-    // so self.maps has not been updated for this program.
-    // If the user provided a UUID of program to share a map with,
-    // then map the directory is still in use and there is nothing to do.
-    // Otherwise, manage_map_pin_path() created the map directory so it must
-    // deleted.
-    async fn cleanup_map_pin_path(
-        &mut self,
-        id: Uuid,
-        map_owner_uuid: Option<Uuid>,
-    ) -> Result<(), BpfdError> {
-        let (map_owner, map_pin_path) = calc_map_pin_path(id, map_owner_uuid);
-
-        if map_owner {
-            let _ = fs::remove_dir_all(map_pin_path.clone())
-                .await
-                .map_err(|e| BpfdError::Error(format!("can't delete map dir: {e}")));
-            Ok(())
-        } else {
-            Ok(())
+            Err(map_not_found(owner_uuid))
         }
     }
 
@@ -1202,7 +3280,7 @@ impl BpfManager {
         map_owner_uuid: Option<Uuid>,
         map_pin_path: String,
     ) -> Result<(), BpfdError> {
-        let (map_owner, _) = get_map_index(id, map_owner_uuid);
+        let (map_owner, map_index) = get_map_index(id, map_owner_uuid);
 
         if map_owner {
             let map = BpfMap {
@@ -1216,10 +3294,175 @@ impl BpfManager {
         } else if let Some(map) = self.maps.get_mut(&map_owner_uuid.unwrap()) {
             map.used_by.push(id);
         } else {
-            return Err(BpfdError::Error(
-                "map_owner_uuid does not exists".to_string(),
-            ));
+            return Err(map_not_found(map_owner_uuid.unwrap()));
+        };
+        self.record_map_metadata(map_index, &map_pin_path);
+        Ok(())
+    }
+
+    // Reads back per-map metadata (name, type, key/value size, max
+    // entries, BTF key/value type ids) for every map pinned under
+    // `map_pin_path` by asking the kernel via `loaded_maps()`, keyed the
+    // same way as `self.maps` so `get_maps` can describe a program's maps
+    // regardless of whether bpfd itself just created them or they were
+    // rediscovered on rebuild_state.
+    fn record_map_metadata(&mut self, map_index: Uuid, map_pin_path: &str) {
+        let pinned_names: std::collections::HashSet<String> = match std::fs::read_dir(map_pin_path)
+        {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .collect(),
+            Err(_) => return,
+        };
+
+        let metadata = loaded_maps()
+            .filter_map(|m| m.ok())
+            .filter_map(|m| {
+                let name = String::from_utf8_lossy(m.name()).into_owned();
+                pinned_names.contains(&name).then_some((m, name))
+            })
+            .map(|(m, name)| MapMetadata {
+                pin_path: format!("{map_pin_path}/{name}"),
+                name,
+                map_type: m.map_type().map(|t| t as u32).unwrap_or_default(),
+                key_size: m.key_size(),
+                value_size: m.value_size(),
+                max_entries: m.max_entries(),
+                btf_id: zero_to_none(m.btf_id()),
+                btf_key_type_id: zero_to_none(m.btf_key_type_id()),
+                btf_value_type_id: zero_to_none(m.btf_value_type_id()),
+            })
+            .collect();
+
+        self.map_names.insert(map_index, metadata);
+        self.record_inner_maps(map_index, map_pin_path);
+    }
+
+    /// Scans `map_pin_path` for `inner_{uuid}` pin subdirectories --
+    /// `calc_inner_map_pin_path`'s layout for an outer map-of-maps' members
+    /// -- and records each via `record_inner_map`, so `is_map_safe_to_delete`
+    /// and `delete_map` know about them even though they were discovered
+    /// from bpffs rather than passed in by whatever created them.
+    fn record_inner_maps(&mut self, map_index: Uuid, map_pin_path: &str) {
+        let Ok(entries) = std::fs::read_dir(map_pin_path) else {
+            return;
         };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Some(inner_index) = entry
+                .file_name()
+                .into_string()
+                .ok()
+                .and_then(|name| name.strip_prefix("inner_").map(str::to_string))
+                .and_then(|suffix| Uuid::parse_str(&suffix).ok())
+            else {
+                continue;
+            };
+            self.record_inner_map(map_index, inner_index);
+        }
+    }
+
+    /// Returns metadata -- name, type, key/value size, max entries, and
+    /// pin path -- for every map associated with `id`'s map owner, so a
+    /// caller can open exactly the map it needs by name instead of
+    /// assuming bpfd's `map_pin_path` directory layout.
+    pub(crate) fn get_maps(
+        &self,
+        id: Uuid,
+        map_owner_uuid: Option<Uuid>,
+    ) -> Result<Vec<MapMetadata>, BpfdError> {
+        let (_, map_index) = get_map_index(id, map_owner_uuid);
+
+        self.map_names
+            .get(&map_index)
+            .cloned()
+            .ok_or_else(|| map_not_found(map_index))
+    }
+
+    // Pre-seeds `id`'s (not-yet-created) map directory with a symlink to
+    // the single `map_name` map already pinned under `donor`'s map
+    // directory, so when `id` is later loaded as its own map owner it
+    // picks up that one map by name -- aya reuses a pre-existing pinned
+    // map of the same name instead of creating a new one -- without
+    // inheriting any of `donor`'s other maps.
+    async fn bind_shared_map(
+        &self,
+        id: Uuid,
+        donor: Uuid,
+        map_name: &str,
+    ) -> Result<(), BpfdError> {
+        self.bind_shared_map_path(id, map_name, &format!("{RTDIR_FS_MAPS}/{donor}/{map_name}"))
+            .await
+    }
+
+    /// Looks up which currently-known map owner exports a map called
+    /// `map_name`, using the metadata `record_map_metadata` already reads
+    /// back from the kernel for every owner, so a caller can bind to a
+    /// shared map by its stable name instead of the owning program's
+    /// opaque UUID. Errors if no owner (or more than one) has a map by
+    /// that name, since binding an ambiguous name would silently pick
+    /// whichever owner `self.map_names` happened to iterate first.
+    pub(crate) fn resolve_shared_map_by_name(&self, map_name: &str) -> Result<Uuid, BpfdError> {
+        let mut owners = self.map_names.iter().filter_map(|(owner, maps)| {
+            maps.iter()
+                .any(|m| m.name == map_name)
+                .then_some(*owner)
+        });
+
+        let owner = owners
+            .next()
+            .ok_or_else(|| BpfdError::Error(format!("no map named {map_name} is pinned")))?;
+
+        if owners.next().is_some() {
+            return Err(BpfdError::Error(format!(
+                "map name {map_name} is ambiguous: pinned by more than one program"
+            )));
+        }
+
+        Ok(owner)
+    }
+
+    /// Binds `id` to a single map by stable name, resolving the owning
+    /// program internally via `resolve_shared_map_by_name` instead of
+    /// requiring the caller to already know its UUID.
+    pub(crate) async fn bind_shared_map_by_name(
+        &self,
+        id: Uuid,
+        map_name: &str,
+    ) -> Result<(), BpfdError> {
+        let donor = self.resolve_shared_map_by_name(map_name)?;
+        self.bind_shared_map(id, donor, map_name).await
+    }
+
+    /// Binds `id` to `map_name` at `source_path`, an already-pinned map
+    /// found anywhere on bpffs -- not necessarily under another
+    /// bpfd-managed program's directory -- so a caller that already knows
+    /// a map's pin path can share it without bpfd resolving an owner UUID
+    /// or name at all. `bind_shared_map` and `bind_shared_map_by_name`
+    /// both funnel through this once they've resolved `source_path`
+    /// themselves.
+    pub(crate) async fn bind_shared_map_path(
+        &self,
+        id: Uuid,
+        map_name: &str,
+        source_path: &str,
+    ) -> Result<(), BpfdError> {
+        if fs::metadata(source_path).await.is_err() {
+            return Err(BpfdError::Error(format!(
+                "pinned map {map_name} not found at {source_path}"
+            )));
+        }
+
+        let (_, map_pin_path) = calc_map_pin_path(id, None);
+        fs::create_dir_all(&map_pin_path)
+            .await
+            .map_err(|e| map_syscall_error("create_dir_all", e))?;
+
+        fs::symlink(source_path, format!("{map_pin_path}/{map_name}"))
+            .await
+            .map_err(|e| map_syscall_error("symlink", e))?;
+
         Ok(())
     }
 
@@ -1228,7 +3471,7 @@ impl BpfManager {
     // returns false if this program is the map owner and other programs
     // are referencing the map, true otherwise.
     fn is_map_safe_to_delete(&mut self, id: Uuid, map_owner_uuid: Option<Uuid>) -> bool {
-        let (map_owner, _) = get_map_index(id, map_owner_uuid);
+        let (map_owner, map_index) = get_map_index(id, map_owner_uuid);
 
         if map_owner {
             // If this eBPF program is eBPF program that created the map,
@@ -1243,9 +3486,44 @@ impl BpfManager {
             }
         }
 
+        // Refuse to delete a map that's still listed as an inner map of a
+        // live outer map-of-maps, even if no program directly references
+        // it -- the outer map's kernel fd still holds it open.
+        if self.is_inner_map(map_index) {
+            return false;
+        }
+
         true
     }
 
+    /// True if `map_index` is recorded as an inner map of any outer
+    /// map-of-maps still present in `self.maps`.
+    fn is_inner_map(&self, map_index: Uuid) -> bool {
+        self.inner_maps
+            .iter()
+            .any(|(outer, inners)| self.maps.contains_key(outer) && inners.contains(&map_index))
+    }
+
+    /// Registers `inner_index` (a key into `self.maps`, same as any
+    /// top-level map) as an inner map of `outer_index`'s
+    /// `BPF_MAP_TYPE_ARRAY_OF_MAPS`/`BPF_MAP_TYPE_HASH_OF_MAPS` map, so
+    /// `is_map_safe_to_delete` refuses to unload it out from under the
+    /// outer map and `delete_map` cleans it up once the outer map goes
+    /// away.
+    pub(crate) fn record_inner_map(&mut self, outer_index: Uuid, inner_index: Uuid) {
+        let inners = self.inner_maps.entry(outer_index).or_default();
+        if !inners.contains(&inner_index) {
+            inners.push(inner_index);
+        }
+    }
+
+    /// Per-inner-map pin subdirectory for an outer map-of-maps, mirroring
+    /// `calc_map_pin_path`'s UUID-keyed layout one level down so each
+    /// inner map gets its own directory under the outer map's.
+    pub(crate) fn calc_inner_map_pin_path(outer_map_pin_path: &str, inner_index: Uuid) -> String {
+        format!("{outer_map_pin_path}/inner_{inner_index}")
+    }
+
     // This function cleans up a map entry when an eBPF program is
     // being unloaded. If the eBPF program is the map owner, then
     // the map is removed from the hash table and the associated
@@ -1267,12 +3545,33 @@ impl BpfManager {
             if map.used_by.is_empty() {
                 let (_, path) = calc_map_pin_path(id, map_owner_uuid);
                 self.maps.remove(&map_index.clone());
+                self.map_names.remove(&map_index);
                 fs::remove_dir_all(path)
                     .await
-                    .map_err(|e| BpfdError::Error(format!("can't delete map dir: {e}")))?;
+                    .map_err(|e| map_syscall_error("remove_dir_all", e))?;
+
+                // The outer map itself is gone; any inner maps it held are
+                // no longer pinned under anything and can be reclaimed as
+                // long as nothing else still references them.
+                if let Some(inners) = self.inner_maps.remove(&map_index) {
+                    for inner_index in inners {
+                        if self.is_inner_map(inner_index) {
+                            // Still referenced by a different live outer map.
+                            continue;
+                        }
+                        if let Some(inner_map) = self.maps.get(&inner_index) {
+                            if inner_map.used_by.is_empty() {
+                                let inner_path = inner_map.map_pin_path.clone();
+                                self.maps.remove(&inner_index);
+                                self.map_names.remove(&inner_index);
+                                let _ = fs::remove_dir_all(inner_path).await;
+                            }
+                        }
+                    }
+                }
             }
         } else {
-            return Err(BpfdError::Error("map_pin_path does not exists".to_string()));
+            return Err(map_not_found(map_index));
         }
 
         Ok(())
@@ -1292,6 +3591,124 @@ impl BpfManager {
             self.maps.insert(id, map);
         }
     }
+
+    /// Resolves a stable name previously registered via `name_map_owner`
+    /// back to its owner UUID, so a caller (e.g. a `map_owner_name` CLI
+    /// flag) can reference an owner's map directory by name instead of
+    /// UUID.
+    pub(crate) fn resolve_map_owner_name(&self, name: &str) -> Result<Uuid, BpfdError> {
+        self.map_owner_names
+            .get(name)
+            .copied()
+            .ok_or_else(|| BpfdError::Error(format!("no map owner named {name}")))
+    }
+
+    /// Declares `name` as a stable alias for `owner`'s map directory,
+    /// recording it in `self.map_owner_names` and mirroring it on bpffs as
+    /// `{RTDIR_FS_MAPS}/by-name/{name}`, a symlink to `owner`'s UUID-keyed
+    /// map directory. A program that owns a map can be named once this
+    /// way, after which later programs may set `map_owner_uuid` by
+    /// resolving this name instead of needing the owner's generated UUID.
+    /// Errors if `name` is already claimed by a different owner.
+    pub(crate) async fn name_map_owner(
+        &mut self,
+        name: String,
+        owner: Uuid,
+    ) -> Result<(), BpfdError> {
+        if let Some(existing) = self.map_owner_names.get(&name) {
+            return if *existing == owner {
+                Ok(())
+            } else {
+                Err(BpfdError::Error(format!(
+                    "map owner name {name} is already in use"
+                )))
+            };
+        }
+
+        let by_name_dir = format!("{RTDIR_FS_MAPS}/by-name");
+        fs::create_dir_all(&by_name_dir)
+            .await
+            .map_err(|e| map_syscall_error("create_dir_all", e))?;
+
+        fs::symlink(
+            format!("{RTDIR_FS_MAPS}/{owner}"),
+            format!("{by_name_dir}/{name}"),
+        )
+        .await
+        .map_err(|e| map_syscall_error("symlink", e))?;
+
+        self.map_owner_names.insert(name, owner);
+        Ok(())
+    }
+
+    /// Repopulates `self.map_owner_names` on restart from the `by-name`
+    /// symlinks `name_map_owner` left on bpffs, since the in-memory table
+    /// doesn't survive a daemon restart.
+    async fn rebuild_map_owner_names(&mut self) {
+        let by_name_dir = format!("{RTDIR_FS_MAPS}/by-name");
+        let Ok(mut entries) = fs::read_dir(&by_name_dir).await else {
+            return;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Ok(target) = fs::read_link(entry.path()).await else {
+                continue;
+            };
+            let Some(owner) = target
+                .file_name()
+                .and_then(|f| f.to_str())
+                .and_then(|s| s.parse().ok())
+            else {
+                continue;
+            };
+            self.map_owner_names.insert(name, owner);
+        }
+    }
+}
+
+/// Decodes one aya-log wire-format record from a raw `AYA_LOGS` perf
+/// buffer sample into a `LogRecord`. The wire format is a sequence of
+/// length-tagged fields: a `u8` tag identifying the field, a little-endian
+/// `u16` length, then that many bytes of UTF-8 payload. Fields are read
+/// until the buffer is exhausted; unrecognized tags are still skipped by
+/// their declared length rather than failing the whole record, so a newer
+/// producer's extra fields don't break an older bpfd build. Returns `None`
+/// if a field's declared length runs past the end of the buffer, since a
+/// truncated record can't be trusted.
+fn decode_log_record(buf: &[u8]) -> Option<LogRecord> {
+    const TAG_LEVEL: u8 = 1;
+    const TAG_TARGET: u8 = 2;
+    const TAG_MESSAGE: u8 = 3;
+
+    let mut level = String::from("INFO");
+    let mut target = String::new();
+    let mut message = String::new();
+
+    let mut pos = 0;
+    while pos + 3 <= buf.len() {
+        let tag = buf[pos];
+        let len = u16::from_le_bytes([buf[pos + 1], buf[pos + 2]]) as usize;
+        let start = pos + 3;
+        let end = start + len;
+        if end > buf.len() {
+            return None;
+        }
+        let field = String::from_utf8_lossy(&buf[start..end]).into_owned();
+        match tag {
+            TAG_LEVEL => level = field,
+            TAG_TARGET => target = field,
+            TAG_MESSAGE => message = field,
+            _ => {}
+        }
+        pos = end;
+    }
+
+    Some(LogRecord {
+        level,
+        target,
+        message,
+    })
 }
 
 // map_index is a UUID. It is either the programs UUID, or the UUID
@@ -1310,12 +3727,332 @@ fn get_map_index(id: Uuid, map_owner_uuid: Option<Uuid>) -> (bool, Uuid) {
 // map_pin_path is a the directory the maps are located. Currently, it
 // is a fixed bpfd location containing the map_index, which is a UUID.
 // The UUID is either the programs UUID, or the UUID of another program
-// that map_owner_uuid references.
+// that map_owner_uuid references. A caller that only has a human-readable
+// owner name (not a UUID) should resolve it with
+// `BpfManager::resolve_map_owner_name` first -- e.g. the symlinks under
+// `{RTDIR_FS_MAPS}/by-name` -- and pass the resulting UUID in here as
+// `map_owner_uuid`.
 pub fn calc_map_pin_path(id: Uuid, map_owner_uuid: Option<Uuid>) -> (bool, String) {
     let (map_owner, map_index) = get_map_index(id, map_owner_uuid);
     (map_owner, format!("{RTDIR_FS_MAPS}/{}", map_index))
 }
 
+// `BpfdError` doesn't carry dedicated map-syscall-error variants yet --
+// every map failure bottoms out in the catch-all `BpfdError::Error(String)`
+// -- so these constructors at least give every map-related failure a
+// consistent, greppable shape (`MapNotFound`/`MapInUse`/`MapSyscallError`)
+// instead of each call site hand-rolling its own message. Once
+// `BpfdError` grows real variants for these, these can become thin
+// wrappers around them (or be inlined away entirely).
+
+/// The map directory keyed by `map_index` isn't tracked in `self.maps`.
+fn map_not_found(map_index: Uuid) -> BpfdError {
+    BpfdError::Error(format!("MapNotFound: no map for {map_index}"))
+}
+
+/// The map keyed by `map_index` is still referenced by another program and
+/// can't be unloaded out from under it.
+fn map_in_use(map_index: Uuid) -> BpfdError {
+    BpfdError::Error(format!("MapInUse: map {map_index} is still referenced"))
+}
+
+/// A filesystem syscall (`create_dir_all`, `remove_dir_all`, `symlink`, ...)
+/// against a map's pin directory failed.
+fn map_syscall_error(op: &str, source: impl std::fmt::Display) -> BpfdError {
+    BpfdError::Error(format!("MapSyscallError: {op}: {source}"))
+}
+
+/// The kernel reports an unset BTF type id as `0`; normalize that to
+/// `None` so callers don't mistake it for a real id.
+fn zero_to_none(id: u32) -> Option<u32> {
+    (id != 0).then_some(id)
+}
+
+// cgroup_skb re-uses the same Direction enum as TC: ingress packets are
+// filtered on the way into the cgroup, egress on the way out.
+fn cgroup_skb_attach_type(direction: Direction) -> CgroupSkbAttachType {
+    match direction {
+        Ingress => CgroupSkbAttachType::Ingress,
+        Egress => CgroupSkbAttachType::Egress,
+    }
+}
+
+// cgroup_sock attach points aren't ordered like TC/cgroup_skb, so they're
+// taken as a string (e.g. "connect4", "bind6") straight off the load args
+// rather than a bpfd-defined enum.
+fn parse_cgroup_sock_attach_type(attach_type: &str) -> Result<CgroupSockAttachType, BpfdError> {
+    match attach_type {
+        "post_bind4" => Ok(CgroupSockAttachType::PostBind4),
+        "post_bind6" => Ok(CgroupSockAttachType::PostBind6),
+        "bind4" => Ok(CgroupSockAttachType::Bind4),
+        "bind6" => Ok(CgroupSockAttachType::Bind6),
+        "connect4" => Ok(CgroupSockAttachType::Connect4),
+        "connect6" => Ok(CgroupSockAttachType::Connect6),
+        _ => Err(BpfdError::InvalidAttach),
+    }
+}
+
+// Builds the `Program` a bundle section should be loaded as, inferring its
+// kind and attach target from the section's libbpf-style `SEC()` name:
+// `xdp`, `classifier`, `kprobe/<fn>`, `kretprobe/<fn>`,
+// `uprobe/<target>:<fn>`, `uretprobe/<target>:<fn>`, and
+// `tracepoint/<category>/<name>`. Returns `Ok(None)` for sections bpfd
+// doesn't know how to auto-attach (e.g. helper subprograms that aren't
+// directly loadable), so the caller can skip them instead of failing the
+// whole bundle.
+#[allow(clippy::too_many_arguments)]
+async fn infer_bundle_program(
+    section_name: &str,
+    loaded_program: &LoadedProgram,
+    location: command::Location,
+    global_data: HashMap<String, Vec<u8>>,
+    map_owner_uuid: Option<Uuid>,
+    username: String,
+    args: &LoadBundleArgs,
+) -> Result<Option<Program>, BpfdError> {
+    let data = ProgramData::new(
+        location,
+        section_name.to_string(),
+        global_data,
+        map_owner_uuid,
+        username,
+    )
+    .await?;
+
+    let prog = match loaded_program {
+        LoadedProgram::Xdp(_) => {
+            let template = args.xdp_template.as_ref().ok_or_else(|| {
+                BpfdError::Error(format!(
+                    "bundle section {section_name} is an xdp program but no interface was configured"
+                ))
+            })?;
+            let mut info = template.clone();
+            info.metadata.name = section_name.to_string();
+            info.metadata.attached = false;
+            info.current_position = None;
+            Program::Xdp(XdpProgram { data, info })
+        }
+        LoadedProgram::SchedClassifier(_) => {
+            let template = args.tc_template.as_ref().ok_or_else(|| {
+                BpfdError::Error(format!(
+                    "bundle section {section_name} is a tc program but no interface was configured"
+                ))
+            })?;
+            let mut info = template.clone();
+            info.metadata.name = section_name.to_string();
+            info.metadata.attached = false;
+            info.current_position = None;
+            Program::Tc(TcProgram { data, info })
+        }
+        LoadedProgram::TracePoint(_) => {
+            let Some(rest) = section_name.strip_prefix("tracepoint/") else {
+                return Ok(None);
+            };
+            let Some((category, name)) = rest.split_once('/') else {
+                return Err(BpfdError::InvalidAttach(section_name.to_string()));
+            };
+            Program::Tracepoint(TracepointProgram {
+                data,
+                info: TracepointProgramInfo {
+                    tracepoint: format!("{category}/{name}"),
+                },
+            })
+        }
+        LoadedProgram::KProbe(_) => {
+            let (retprobe, fn_name) = if let Some(fn_name) = section_name.strip_prefix("kretprobe/")
+            {
+                (true, fn_name)
+            } else if let Some(fn_name) = section_name.strip_prefix("kprobe/") {
+                (false, fn_name)
+            } else {
+                return Ok(None);
+            };
+            Program::Kprobe(KprobeProgram {
+                data,
+                info: KprobeProgramInfo {
+                    fn_name: fn_name.to_string(),
+                    offset: 0,
+                    retprobe,
+                    namespace: None,
+                },
+            })
+        }
+        LoadedProgram::UProbe(_) => {
+            let (retprobe, rest) = if let Some(rest) = section_name.strip_prefix("uretprobe/") {
+                (true, rest)
+            } else if let Some(rest) = section_name.strip_prefix("uprobe/") {
+                (false, rest)
+            } else {
+                return Ok(None);
+            };
+            let Some((target, fn_name)) = rest.rsplit_once(':') else {
+                return Err(BpfdError::InvalidAttach(section_name.to_string()));
+            };
+            Program::Uprobe(UprobeProgram {
+                data,
+                info: UprobeProgramInfo {
+                    fn_name: Some(fn_name.to_string()),
+                    offset: 0,
+                    target: target.to_string(),
+                    retprobe,
+                    pid: None,
+                    namespace: None,
+                },
+            })
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(prog))
+}
+
+// Resolves `target`'s on-disk path as seen from the mount namespace
+// identified by `namespace`, if any is given. A purely numeric namespace is
+// treated as the PID of a process in the target namespace, and the path is
+// read through that process's /proc/<pid>/root so bpfd never has to enter
+// the namespace itself; anything else is treated as a container runtime's
+// already-resolved root path that `target` is relative to.
+fn resolve_namespaced_target(
+    target: &str,
+    namespace: &Option<String>,
+) -> Result<String, BpfdError> {
+    let Some(namespace) = namespace else {
+        return Ok(target.to_string());
+    };
+
+    let root = if namespace.chars().all(|c| c.is_ascii_digit()) {
+        format!("/proc/{namespace}/root")
+    } else {
+        namespace.clone()
+    };
+
+    let resolved = format!("{root}{target}");
+    if !std::path::Path::new(&resolved).exists() {
+        return Err(BpfdError::Error(format!(
+            "uprobe target {target} not found in namespace {namespace}"
+        )));
+    }
+
+    Ok(resolved)
+}
+
+/// A single `.note.stapsdt` entry: a provider+probe name pair, the probe's
+/// link-time virtual address, the note's own base address, and (if the
+/// probe has an is-enabled check) the semaphore's virtual address.
+struct UsdtNote {
+    provider: String,
+    probe: String,
+    pc: u64,
+    base: u64,
+    semaphore: u64,
+}
+
+/// A resolved USDT attach point: the file offset to give to `UProbe::attach`
+/// and, if the probe has a semaphore, the `ref_ctr_offset` to pass so the
+/// kernel bumps the inferior's is-enabled counter while the probe is armed.
+struct UsdtProbe {
+    offset: u64,
+    ref_ctr_offset: Option<u64>,
+}
+
+/// Parses the `.note.stapsdt` SHT_NOTE section of `target`'s ELF image into
+/// the list of USDT probes it advertises. Each note's description is laid
+/// out as three native-endian addresses (probe PC, base address, semaphore
+/// address) followed by three NUL-terminated strings (provider, probe name,
+/// and an argument format bpfd doesn't need).
+fn parse_stapsdt_notes(elf: &Elf, data: &[u8]) -> Result<Vec<UsdtNote>, BpfdError> {
+    const NT_STAPSDT: u32 = 3;
+    let mut notes = Vec::new();
+
+    for iter in elf.iter_note_sections(data, Some(".note.stapsdt")) {
+        for note in iter {
+            let note: Note =
+                note.map_err(|e| BpfdError::Error(format!("malformed USDT note: {e}")))?;
+            if note.n_type != NT_STAPSDT {
+                continue;
+            }
+
+            let desc = note.desc;
+            if desc.len() < 24 {
+                continue;
+            }
+            let pc = u64::from_ne_bytes(desc[0..8].try_into().unwrap());
+            let base = u64::from_ne_bytes(desc[8..16].try_into().unwrap());
+            let semaphore = u64::from_ne_bytes(desc[16..24].try_into().unwrap());
+
+            let strings = &desc[24..];
+            let mut parts = strings.split(|b| *b == 0).filter(|s| !s.is_empty());
+            let provider = parts
+                .next()
+                .map(|s| String::from_utf8_lossy(s).into_owned())
+                .unwrap_or_default();
+            let probe = parts
+                .next()
+                .map(|s| String::from_utf8_lossy(s).into_owned())
+                .unwrap_or_default();
+
+            notes.push(UsdtNote {
+                provider,
+                probe,
+                pc,
+                base,
+                semaphore,
+            });
+        }
+    }
+
+    Ok(notes)
+}
+
+/// Resolves `provider`:`probe` in `target`'s ELF image to a file offset and,
+/// if the probe has an is-enabled semaphore, a `ref_ctr_offset`.
+fn resolve_usdt_probe(target: &str, provider: &str, probe: &str) -> Result<UsdtProbe, BpfdError> {
+    let bytes = std::fs::read(target)
+        .map_err(|e| BpfdError::Error(format!("unable to read usdt target {target}: {e}")))?;
+    let elf = Elf::parse(&bytes)
+        .map_err(|e| BpfdError::Error(format!("unable to parse usdt target {target}: {e}")))?;
+
+    let notes = parse_stapsdt_notes(&elf, &bytes)?;
+    let note = notes
+        .iter()
+        .find(|n| n.provider == provider && n.probe == probe)
+        .ok_or_else(|| {
+            BpfdError::Error(format!(
+                "usdt probe {provider}:{probe} not found in {target}"
+            ))
+        })?;
+
+    // The note's addresses are link-time virtual addresses; recover the
+    // file offset by finding the loadable segment that covers the base
+    // address and subtracting its own virtual-address-to-file-offset delta.
+    let program_header = elf
+        .program_headers
+        .iter()
+        .find(|ph| {
+            ph.p_type == goblin::elf::program_header::PT_LOAD
+                && note.base >= ph.p_vaddr
+                && note.base < ph.p_vaddr + ph.p_memsz
+        })
+        .ok_or_else(|| {
+            BpfdError::Error(format!(
+                "unable to locate segment for usdt probe {provider}:{probe}"
+            ))
+        })?;
+    let delta = program_header.p_vaddr - program_header.p_offset;
+    let offset = note.pc - delta;
+
+    let ref_ctr_offset = if note.semaphore != 0 {
+        Some(note.semaphore - delta)
+    } else {
+        None
+    };
+
+    Ok(UsdtProbe {
+        offset,
+        ref_ctr_offset,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use uuid::{uuid, Uuid};
@@ -1,35 +1,45 @@
 // SPDX-License-Identifier: (MIT OR Apache-2.0)
 // Copyright Authors of bpfd
 
-use aya::include_bytes_aligned;
-use bpfd::server::{config_from_file, programs_from_directory, serve};
-use log::warn;
-use nix::{
-    libc::RLIM_INFINITY,
-    sys::resource::{setrlimit, Resource},
-};
-use simplelog::{ColorChoice, ConfigBuilder, LevelFilter, TermLogger, TerminalMode};
+use aya::{include_bytes_aligned, Btf};
+use bpfd::server::{config_from_file, policy, programs_from_directory, serve};
+use bpfd_api::config::{Config, LogSink};
+use log::{info, warn, LevelFilter};
+use nix::sys::resource::{setrlimit, Resource};
+use simplelog::{ColorChoice, ConfigBuilder, TermLogger, TerminalMode, WriteLogger};
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Raised `RLIMIT_MEMLOCK` for kernels that still charge BPF allocations
+/// against it instead of the memory cgroup, used unless `[rlimit]
+/// memlock_bytes` in bpfd.toml overrides it. Chosen as a generous but
+/// finite bound rather than `RLIM_INFINITY`, since an unbounded limit on
+/// those kernels lets a buggy or malicious load pin down memory the
+/// kernel would otherwise reclaim.
+const DEFAULT_MEMLOCK_LIMIT_BYTES: u64 = 128 * 1024 * 1024;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    TermLogger::init(
-        LevelFilter::Debug,
-        ConfigBuilder::new()
-            .set_target_level(LevelFilter::Error)
-            .set_location_level(LevelFilter::Error)
-            .add_filter_ignore("h2".to_string())
-            .add_filter_ignore("rustls".to_string())
-            .add_filter_ignore("hyper".to_string())
-            .add_filter_ignore("aya".to_string())
-            .build(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    )?;
+    // Loaded before the logger so `[log]` in bpfd.toml can pick the level,
+    // filters and sink the rest of startup logs through.
+    let config = config_from_file("/etc/bpfd.toml");
+    init_logger(&config);
+
     let dispatcher_bytes =
         include_bytes_aligned!("../../target/bpfel-unknown-none/release/xdp_dispatcher.bpf.o");
-    setrlimit(Resource::RLIMIT_MEMLOCK, RLIM_INFINITY, RLIM_INFINITY).unwrap();
 
-    let config = config_from_file("/etc/bpfd.toml");
+    // The dispatcher (and any user program bpfd loads) is compiled with
+    // BTF retained, so as long as the running kernel exposes its own BTF
+    // bpfd can CO-RE relocate struct field offsets and map definitions
+    // against it instead of requiring an object rebuilt per kernel
+    // version. BpfManager performs the actual relocation per-load; this
+    // is just an early, informative probe so a fleet operator can see in
+    // the log whether a given host will get it.
+    match Btf::from_sys_fs() {
+        Ok(_) => info!("system BTF found, loads will use CO-RE relocation"),
+        Err(e) => warn!("no system BTF available, loads will skip CO-RE relocation: {e}"),
+    }
+    configure_memlock_rlimit(&config);
+    spawn_policy_reload_handler();
 
     let static_programs = match programs_from_directory("/etc/bpfd/programs.d") {
         Ok(static_programs) => static_programs,
@@ -43,3 +53,167 @@ async fn main() -> anyhow::Result<()> {
     serve(config, dispatcher_bytes, static_programs).await?;
     Ok(())
 }
+
+/// Spawns a background task that reloads the RBAC policy file on SIGHUP,
+/// giving `policy::reload`'s "without a restart" claim an actual trigger --
+/// the conventional Unix signal for "re-read your config" that most other
+/// long-running daemons already honor.
+fn spawn_policy_reload_handler() {
+    tokio::spawn(async {
+        let mut sighup = match signal(SignalKind::hangup()) {
+            Ok(sighup) => sighup,
+            Err(e) => {
+                warn!("failed to install SIGHUP handler, policy reload will require a restart: {e}");
+                return;
+            }
+        };
+        loop {
+            sighup.recv().await;
+            info!("received SIGHUP, reloading RBAC policy");
+            policy::reload();
+        }
+    });
+}
+
+/// Sets up the `log` backend from `[log]` in `bpfd.toml`: level, extra
+/// per-module filters on top of the noisy crates bpfd always quiets, and
+/// a sink (terminal, a file, or the systemd journal). A daemonized
+/// deployment shouldn't fail to start just because, say, the configured
+/// log file's parent directory doesn't exist yet, so an unavailable sink
+/// falls back to stderr with a warning rather than aborting startup --
+/// the way aya-based loaders avoid failing on logger init.
+fn init_logger(config: &Config) {
+    let log_config = config.log.clone().unwrap_or_default();
+    let level = parse_level(&log_config.level);
+
+    let mut builder = ConfigBuilder::new();
+    builder
+        .set_target_level(LevelFilter::Error)
+        .set_location_level(LevelFilter::Error)
+        .add_filter_ignore("h2".to_string())
+        .add_filter_ignore("rustls".to_string())
+        .add_filter_ignore("hyper".to_string())
+        .add_filter_ignore("aya".to_string());
+    for module in &log_config.filter_ignore {
+        builder.add_filter_ignore(module.clone());
+    }
+    let simplelog_config = builder.build();
+
+    let started = match log_config.sink {
+        LogSink::Terminal => TermLogger::init(
+            level,
+            simplelog_config.clone(),
+            TerminalMode::Mixed,
+            ColorChoice::Auto,
+        )
+        .is_ok(),
+        LogSink::File(ref path) => std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .ok()
+            .and_then(|f| WriteLogger::init(level, simplelog_config.clone(), f).ok())
+            .is_some(),
+        LogSink::Journald => systemd_journal_logger::init()
+            .map(|_| log::set_max_level(level))
+            .is_ok(),
+    };
+
+    if !started {
+        // Losing every log line for the rest of the process's life is
+        // worse than an unstructured one, so fall back to stderr instead
+        // of aborting startup over a sink that didn't come up.
+        let _ = WriteLogger::init(level, simplelog_config, std::io::stderr());
+        eprintln!("bpfd: configured log sink unavailable, falling back to stderr");
+    }
+}
+
+/// Parses `[log] level` from bpfd.toml, defaulting (and warning, since
+/// this runs before the logger is up) to `debug` on anything unrecognized
+/// rather than refusing to start.
+fn parse_level(level: &str) -> LevelFilter {
+    level.parse().unwrap_or_else(|_| {
+        eprintln!("bpfd: invalid log level {level:?} in bpfd.toml, defaulting to debug");
+        LevelFilter::Debug
+    })
+}
+
+/// Raises `RLIMIT_MEMLOCK` only on kernels that actually need it.
+///
+/// Kernels from roughly 5.11 onward account BPF map and program memory
+/// against the memory cgroup instead of the calling process's memlock
+/// limit, so bumping the limit on those hosts is a no-op bpfd used to pay
+/// for anyway (and, at `RLIM_INFINITY`, a needless footgun). Rather than
+/// branch on the kernel version directly, this probes for the behavior
+/// the same way libbpf does: it tries to create a throwaway map with the
+/// process's current (typically small, e.g. 64KiB default) limit left
+/// untouched. If that succeeds, the limit clearly isn't being enforced
+/// against BPF allocations, so it's left alone; otherwise it's raised to
+/// `[rlimit] memlock_bytes` (default [`DEFAULT_MEMLOCK_LIMIT_BYTES`]).
+fn configure_memlock_rlimit(config: &Config) {
+    if probe_memcg_account() {
+        info!("kernel accounts BPF memory against the memory cgroup, leaving RLIMIT_MEMLOCK untouched");
+        return;
+    }
+
+    let limit = config
+        .rlimit
+        .clone()
+        .and_then(|r| r.memlock_bytes)
+        .unwrap_or(DEFAULT_MEMLOCK_LIMIT_BYTES);
+    info!(
+        "kernel enforces RLIMIT_MEMLOCK against BPF memory (pre-5.11), raising it to {limit} bytes"
+    );
+    if let Err(e) = setrlimit(Resource::RLIMIT_MEMLOCK, limit, limit) {
+        warn!("failed to raise RLIMIT_MEMLOCK to {limit} bytes: {e}");
+    }
+}
+
+/// Creates and immediately drops a one-entry array map to detect whether
+/// the kernel charges BPF memory to the memory cgroup rather than
+/// `RLIMIT_MEMLOCK`. Goes through the raw `bpf()` syscall instead of aya,
+/// since the probe needs to run before anything else touches the limit
+/// and a single-map create/close isn't worth pulling in a loader for.
+fn probe_memcg_account() -> bool {
+    #[repr(C)]
+    struct MapCreateAttr {
+        map_type: u32,
+        key_size: u32,
+        value_size: u32,
+        max_entries: u32,
+        map_flags: u32,
+    }
+
+    const BPF_MAP_CREATE: i64 = 0;
+    const BPF_MAP_TYPE_ARRAY: u32 = 2;
+
+    let mut attr = MapCreateAttr {
+        map_type: BPF_MAP_TYPE_ARRAY,
+        key_size: 4,
+        value_size: 4,
+        max_entries: 1,
+        map_flags: 0,
+    };
+
+    // SAFETY: `attr` is a valid, fully-initialized `bpf_attr` union member
+    // for `BPF_MAP_CREATE`, sized and pointed to correctly for the
+    // duration of the syscall.
+    let ret = unsafe {
+        nix::libc::syscall(
+            nix::libc::SYS_bpf,
+            BPF_MAP_CREATE,
+            &mut attr as *mut MapCreateAttr,
+            std::mem::size_of::<MapCreateAttr>() as u32,
+        )
+    };
+
+    if ret < 0 {
+        return false;
+    }
+    // SAFETY: a non-negative return from BPF_MAP_CREATE is an owned fd for
+    // the map just created, which must be closed to avoid leaking it.
+    unsafe {
+        nix::libc::close(ret as i32);
+    }
+    true
+}
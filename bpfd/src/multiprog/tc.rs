@@ -10,7 +10,7 @@ use aya::{
         tc::{self, SchedClassifierLink, TcOptions},
         Extension, Link, SchedClassifier, TcAttachType,
     },
-    Bpf, BpfLoader,
+    Bpf, BpfLoader, Btf,
 };
 use bpfd_api::util::directories::*;
 use log::debug;
@@ -77,8 +77,14 @@ impl TcDispatcher {
 
         debug!("tc dispatcher config: {:?}", config);
 
+        // Compiled with bpf-linker's `--btf`, so relocating the dispatcher
+        // against the running kernel's BTF (when present) lets the same
+        // object run unmodified across kernels whose struct layouts have
+        // shifted since it was built.
+        let btf = Btf::from_sys_fs().ok();
         let mut loader = BpfLoader::new()
             .set_global("CONFIG", &config, true)
+            .btf(btf.as_ref())
             .load(DISPATCHER_BYTES)?;
 
         let dispatcher: &mut SchedClassifier = loader
@@ -266,11 +272,71 @@ impl TcDispatcher {
         let path = format!("{dir}/{if_index}_{revision}");
         let file = fs::File::open(path)?;
         let reader = BufReader::new(file);
-        let prog = serde_json::from_reader(reader)?;
-        // TODO: We should check the bpffs paths here to for pinned links etc...
+        let prog: TcDispatcher = serde_json::from_reader(reader)?;
+        prog.reconcile_pins()?;
         Ok(prog)
     }
 
+    /// Cross-checks this dispatcher's bpffs state against what's actually
+    /// pinned, repairing drift a crash mid revision-swap can leave behind:
+    /// any `link_*` pin under our own `dispatcher_{if_index}_{revision}`
+    /// directory that no longer reopens is unpinned so a later
+    /// `attach_extensions` doesn't trip over a stale file, and any sibling
+    /// `dispatcher_{if_index}_*` directory with no matching saved revision
+    /// state is a zombie left by a revision that never finished swapping in
+    /// and gets removed outright.
+    fn reconcile_pins(&self) -> Result<(), BpfdError> {
+        let fs_base = match self.direction {
+            Direction::Ingress => RTDIR_FS_TC_INGRESS,
+            Direction::Egress => RTDIR_FS_TC_EGRESS,
+        };
+        let state_dir = match self.direction {
+            Direction::Ingress => RTDIR_TC_INGRESS_DISPATCHER,
+            Direction::Egress => RTDIR_TC_EGRESS_DISPATCHER,
+        };
+        let our_dir_name = format!("dispatcher_{}_{}", self.if_index, self.revision);
+        let if_index_prefix = format!("dispatcher_{}_", self.if_index);
+
+        let entries = match fs::read_dir(fs_base) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(BpfdError::Error(format!("unable to reconcile bpffs state: {e}"))),
+        };
+
+        for entry in entries {
+            let entry = entry.map_err(|e| BpfdError::Error(format!("{e}")))?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if name == our_dir_name {
+                let links = match fs::read_dir(entry.path()) {
+                    Ok(links) => links,
+                    Err(_) => continue,
+                };
+                for link in links {
+                    let link = link.map_err(|e| BpfdError::Error(format!("{e}")))?;
+                    if FdLink::from_pin(link.path()).is_err() {
+                        debug!(
+                            "removing stale link pin {:?} left from a previous run",
+                            link.path()
+                        );
+                        let _ = fs::remove_file(link.path());
+                    }
+                }
+            } else if let Some(revision) = name.strip_prefix(&if_index_prefix) {
+                let state_path = format!("{state_dir}/{}_{}", self.if_index, revision);
+                if fs::metadata(&state_path).is_err() {
+                    debug!(
+                        "removing orphaned dispatcher dir {:?} left from a crashed revision swap",
+                        entry.path()
+                    );
+                    let _ = fs::remove_dir_all(entry.path());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn delete(&mut self, full: bool) -> Result<(), BpfdError> {
         debug!(
             "TcDispatcher::delete() for if_index {}, revision {}",
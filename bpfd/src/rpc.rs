@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: (MIT OR Apache-2.0)
 // Copyright Authors of bpfd
-use std::sync::{Arc, Mutex};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 
 use bpfd_api::{
     v1::{
@@ -8,18 +11,20 @@ use bpfd_api::{
         load_request,
         load_request_common::Location,
         loader_server::Loader,
-        ListRequest, ListResponse, LoadRequest, LoadResponse, TcAttachInfo, TracepointAttachInfo,
-        UnloadRequest, UnloadResponse, XdpAttachInfo, NoAttachInfo, NoLocation
+        GetLogsRequest, ListRequest, ListResponse, LoadRequest, LoadResponse, LogRecord,
+        TcAttachInfo, TracepointAttachInfo, UnloadRequest, UnloadResponse, VersionRequest,
+        VersionResponse, XdpAttachInfo, NoAttachInfo, NoLocation
     },
     TcProceedOn, XdpProceedOn,
 };
 use log::{warn, debug};
 use tokio::sync::{mpsc, mpsc::Sender, oneshot};
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 
 use crate::{
-    command::{Command, LoadTCArgs, LoadTracepointArgs, LoadXDPArgs, UnloadArgs},
+    command::{Command, LoadTCArgs, LoadTracepointArgs, LoadXDPArgs, LogsArgs, UnloadArgs},
     oci_utils::BytecodeImage,
 };
 
@@ -32,6 +37,11 @@ static DEFAULT_USER: User = User {
     username: String::new(),
 };
 
+/// bpfd's own protocol version, reported to clients over the `version`
+/// handshake. Only the major component is meant to be compared; bump it on
+/// breaking RPC changes.
+const PROTOCOL_VERSION: &str = "1.0.0";
+
 #[derive(Debug)]
 pub struct BpfdLoader {
     tx: Arc<Mutex<Sender<Command>>>,
@@ -46,6 +56,30 @@ impl BpfdLoader {
 
 #[tonic::async_trait]
 impl Loader for BpfdLoader {
+    type GetLogsStream = Pin<Box<dyn Stream<Item = Result<LogRecord, Status>> + Send>>;
+
+    async fn version(
+        &self,
+        request: Request<VersionRequest>,
+    ) -> Result<Response<VersionResponse>, Status> {
+        debug!(
+            "version handshake with client running protocol {}",
+            request.into_inner().client_version
+        );
+
+        Ok(Response::new(VersionResponse {
+            version: PROTOCOL_VERSION.to_string(),
+            // The `load` handler below only matches Xdp/Tc/Tracepoint attach
+            // info, so USDT loads would be rejected even though bpfctl can
+            // build the request; report it as unsupported until that's wired up.
+            usdt_supported: false,
+            // `get_logs` sends `Command::Logs` to a `process_commands` arm
+            // that actually opens the program's AYA_LOGS perf buffer and
+            // streams decoded records back, so this is safe to advertise.
+            log_streaming_supported: true,
+        }))
+    }
+
     async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
         let mut reply = LoadResponse { id: String::new() };
         let username = request
@@ -299,6 +333,42 @@ impl Loader for BpfdLoader {
             }
         }
     }
+
+    async fn get_logs(
+        &self,
+        request: Request<GetLogsRequest>,
+    ) -> Result<Response<Self::GetLogsStream>, Status> {
+        let request = request.into_inner();
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Logs(LogsArgs {
+            id: request.id,
+            follow: request.follow,
+            responder: resp_tx,
+        });
+
+        let tx = self.tx.lock().unwrap().clone();
+        // Send the GET request
+        tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => match res {
+                Ok(log_rx) => {
+                    let stream = ReceiverStream::new(log_rx).map(Ok);
+                    Ok(Response::new(Box::pin(stream)))
+                }
+                Err(e) => {
+                    warn!("BPFD logs error: {}", e);
+                    Err(Status::aborted(format!("{e}")))
+                }
+            },
+            Err(e) => {
+                warn!("RPC logs error: {}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -379,6 +449,10 @@ mod test {
                 Command::LoadTracepoint(args) => args.responder.send(Ok(Uuid::new_v4())).unwrap(),
                 Command::Unload(args) => args.responder.send(Ok(())).unwrap(),
                 Command::List { responder, .. } => responder.send(Ok(vec![])).unwrap(),
+                Command::Logs(args) => {
+                    let (_log_tx, log_rx) = mpsc::channel(32);
+                    args.responder.send(Ok(log_rx)).unwrap();
+                }
             }
         }
     }
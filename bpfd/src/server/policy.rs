@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+// Copyright Authors of bpfd
+
+//! Per-identity RBAC for the mTLS RPC path, keyed by the client certificate
+//! CN that `intercept` already extracts into [`super::rpc::User`]. A policy
+//! is loaded from a TOML file and cached in memory; [`reload`] re-reads it
+//! so operators can update access rules without restarting the daemon.
+
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{OnceLock, RwLock},
+};
+
+use log::warn;
+use serde::Deserialize;
+use tonic::Status;
+
+const POLICY_PATH: &str = "/etc/bpfd/policy.toml";
+
+static POLICY: OnceLock<RwLock<Policy>> = OnceLock::new();
+
+/// Per-CN access rules, keyed by the exact CN string `intercept` extracts
+/// from the client certificate.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct Policy {
+    #[serde(default, rename = "cn")]
+    by_cn: HashMap<String, CnRules>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct CnRules {
+    #[serde(default)]
+    allow_load: bool,
+    #[serde(default)]
+    allow_unload: bool,
+    #[serde(default)]
+    allow_list: bool,
+    /// Interfaces this CN may load/unload/list on. `None` means any
+    /// interface is allowed.
+    #[serde(default)]
+    allowed_interfaces: Option<Vec<String>>,
+}
+
+/// The operation a caller is attempting, used to pick which `CnRules` flag
+/// to check.
+pub(crate) enum Operation {
+    Load,
+    Unload,
+    List,
+}
+
+fn load_from_disk() -> Policy {
+    match fs::read_to_string(POLICY_PATH) {
+        Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Failed to parse policy file {POLICY_PATH}: {e}; denying all access until it's fixed");
+            Policy::default()
+        }),
+        Err(e) => {
+            warn!("Failed to read policy file {POLICY_PATH}: {e}; denying all access until it's present");
+            Policy::default()
+        }
+    }
+}
+
+fn policy() -> &'static RwLock<Policy> {
+    POLICY.get_or_init(|| RwLock::new(load_from_disk()))
+}
+
+/// Re-reads the policy file from disk, replacing the cached policy in
+/// place. Safe to call at any time (e.g. from a SIGHUP handler) - an
+/// authorization check already in flight sees either the old or the new
+/// policy, never a half-updated one.
+pub(crate) fn reload() {
+    *policy().write().unwrap() = load_from_disk();
+}
+
+/// Checks whether `username` (the mTLS client CN) may perform `op` and, if
+/// `iface` is given, whether they may do so on that interface. A CN with no
+/// policy entry at all is denied everything, so a missing or empty policy
+/// file doesn't silently grant every authenticated client full access.
+pub(crate) fn authorize(username: &str, op: Operation, iface: Option<&str>) -> Result<(), Status> {
+    let policy = policy().read().unwrap();
+    let rules = policy.by_cn.get(username).ok_or_else(|| {
+        Status::permission_denied(format!("no policy entry for client '{username}'"))
+    })?;
+
+    let allowed = match op {
+        Operation::Load => rules.allow_load,
+        Operation::Unload => rules.allow_unload,
+        Operation::List => rules.allow_list,
+    };
+    if !allowed {
+        return Err(Status::permission_denied(format!(
+            "client '{username}' is not authorized to perform this operation"
+        )));
+    }
+
+    if let (Some(iface), Some(allowed_interfaces)) = (iface, &rules.allowed_interfaces) {
+        if !allowed_interfaces.iter().any(|allowed| allowed == iface) {
+            return Err(Status::permission_denied(format!(
+                "client '{username}' is not authorized on interface '{iface}'"
+            )));
+        }
+    }
+
+    Ok(())
+}
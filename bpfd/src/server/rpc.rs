@@ -1,18 +1,23 @@
 // SPDX-License-Identifier: (MIT OR Apache-2.0)
 // Copyright Authors of bpfd
-use std::sync::{Arc, Mutex};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 
 use bpfd_api::v1::{
-    list_response::ListResult, load_request::AttachType, loader_server::Loader, ListRequest,
-    ListResponse, LoadRequest, LoadResponse, UnloadRequest, UnloadResponse,
+    list_response::ListResult, load_request::AttachType, loader_server::Loader, subscribe_response,
+    ListRequest, ListResponse, LoadRequest, LoadResponse, SubscribeRequest, SubscribeResponse,
+    UnloadRequest, UnloadResponse, VersionRequest, VersionResponse,
 };
 use log::warn;
 use tokio::sync::{mpsc, mpsc::Sender, oneshot};
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 use x509_certificate::X509Certificate;
 
-use crate::server::{bpf::InterfaceInfo, errors::BpfdError, pull_bytecode::pull_bytecode};
+use crate::server::{bpf::InterfaceInfo, errors::BpfdError, policy, pull_bytecode::pull_bytecode};
 
 #[derive(Debug, Default)]
 struct User {
@@ -23,8 +28,38 @@ static DEFAULT_USER: User = User {
     username: String::new(),
 };
 
+/// bpfd's own protocol version, reported to clients over the `version`
+/// handshake and checked against the `bpfd-client-version` metadata header
+/// `intercept` looks for on every other request. Only the major component is
+/// meant to be compared; bump it on breaking RPC changes.
+const PROTOCOL_VERSION: &str = "1.0.0";
+
+fn protocol_major(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
+/// Daemon features advertised over the `version` handshake and re-derived
+/// by `intercept` for every later request on the connection, so handlers
+/// can branch on what this build actually supports and return a graceful
+/// `Status::unimplemented` instead of panicking on an unhandled case.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Capabilities {
+    pub(crate) network_multi_attach_supported: bool,
+    pub(crate) event_streaming_supported: bool,
+}
+
+const CAPABILITIES: Capabilities = Capabilities {
+    network_multi_attach_supported: true,
+    event_streaming_supported: true,
+};
+
 /// This function will get called on each inbound request.
-/// It extracts the username from the client certificate and adds it to the request
+/// It extracts the username from the client certificate and adds it to the
+/// request, and - if the client sent a `bpfd-client-version` metadata header
+/// - rejects the request outright when the major protocol version doesn't
+/// match ours, rather than letting a mismatched client hit confusing errors
+/// deeper in the call. Negotiated [`Capabilities`] are attached to every
+/// request so handlers can consult them without re-deriving them.
 pub(crate) fn intercept(mut req: Request<()>) -> Result<Request<()>, Status> {
     let certs = req
         .peer_certs()
@@ -41,7 +76,22 @@ pub(crate) fn intercept(mut req: Request<()>) -> Result<Request<()>, Status> {
         .subject_common_name()
         .ok_or_else(|| Status::unauthenticated("CN is empty"))?;
 
+    if let Some(client_version) = req
+        .metadata()
+        .get("bpfd-client-version")
+        .and_then(|v| v.to_str().ok())
+    {
+        if protocol_major(client_version) != protocol_major(PROTOCOL_VERSION) {
+            return Err(Status::failed_precondition(format!(
+                "client protocol v{} is incompatible with daemon protocol v{}",
+                protocol_major(client_version),
+                protocol_major(PROTOCOL_VERSION),
+            )));
+        }
+    }
+
     req.extensions_mut().insert(User { username });
+    req.extensions_mut().insert(CAPABILITIES);
     Ok(req)
 }
 
@@ -54,6 +104,32 @@ pub struct BpfdLoader {
 /// the command response back to the requester.
 type Responder<T> = oneshot::Sender<T>;
 
+/// One item forwarded to a `subscribe` client, read off a loaded program's
+/// `PerfEventArray`/`RingBuf` map by the per-CPU reader tasks the bpf manager
+/// spawns when it handles [`Command::Subscribe`]. Each reader task sizes its
+/// `BytesMut` pool from the map's own value size rather than a fixed
+/// constant, and is aborted outright (not just left to notice a closed
+/// channel) as soon as its program is unloaded or the client goes away.
+#[derive(Debug)]
+pub(crate) enum Event {
+    /// A length-prefixed copy of one event the program wrote to its map.
+    Sample(Vec<u8>),
+    /// The kernel dropped this many samples on a CPU before the reader task
+    /// could drain them, surfaced distinctly so consumers can detect drops
+    /// instead of silently seeing a gap in the stream.
+    Dropped(u64),
+}
+
+impl From<Event> for SubscribeResponse {
+    fn from(event: Event) -> Self {
+        let event = match event {
+            Event::Sample(data) => subscribe_response::Event::Sample(data),
+            Event::Dropped(count) => subscribe_response::Event::Dropped(count),
+        };
+        SubscribeResponse { event: Some(event) }
+    }
+}
+
 impl BpfdLoader {
     pub(crate) fn new(tx: mpsc::Sender<Command>) -> BpfdLoader {
         let tx = Arc::new(Mutex::new(tx));
@@ -63,6 +139,24 @@ impl BpfdLoader {
 
 #[tonic::async_trait]
 impl Loader for BpfdLoader {
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<SubscribeResponse, Status>> + Send>>;
+
+    async fn version(
+        &self,
+        request: Request<VersionRequest>,
+    ) -> Result<Response<VersionResponse>, Status> {
+        log::debug!(
+            "version handshake with client running protocol {}",
+            request.into_inner().client_version
+        );
+
+        Ok(Response::new(VersionResponse {
+            version: PROTOCOL_VERSION.to_string(),
+            usdt_supported: false,
+            log_streaming_supported: CAPABILITIES.event_streaming_supported,
+        }))
+    }
+
     async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
         let mut reply = LoadResponse { id: String::new() };
         let username = request
@@ -71,6 +165,11 @@ impl Loader for BpfdLoader {
             .unwrap_or(&DEFAULT_USER)
             .username
             .to_string();
+        let capabilities = request
+            .extensions()
+            .get::<Capabilities>()
+            .copied()
+            .unwrap_or(CAPABILITIES);
         let mut request = request.into_inner();
 
         if request.from_image {
@@ -91,17 +190,20 @@ impl Loader for BpfdLoader {
             return Err(Status::aborted("message missing attach_type"));
         }
         let cmd = match request.attach_type.unwrap() {
-            AttachType::NetworkMultiAttach(attach) => Command::Load {
-                iface: attach.iface,
-                responder: resp_tx,
-                path: request.path,
-                program_type: request.program_type,
-                priority: attach.priority,
-                section_name: request.section_name,
-                proceed_on: attach.proceed_on,
-                username,
-            },
-            _ => unimplemented!("attach type not yet implemented"),
+            AttachType::NetworkMultiAttach(attach) if capabilities.network_multi_attach_supported => {
+                policy::authorize(&username, policy::Operation::Load, Some(&attach.iface))?;
+                Command::Load {
+                    iface: attach.iface,
+                    responder: resp_tx,
+                    path: request.path,
+                    program_type: request.program_type,
+                    priority: attach.priority,
+                    section_name: request.section_name,
+                    proceed_on: attach.proceed_on,
+                    username,
+                }
+            }
+            _ => return Err(Status::unimplemented("attach type not supported by this build")),
         };
 
         let tx = self.tx.lock().unwrap().clone();
@@ -145,6 +247,8 @@ impl Loader for BpfdLoader {
             .parse()
             .map_err(|_| Status::invalid_argument("invalid id"))?;
 
+        policy::authorize(&username, policy::Operation::Unload, Some(&request.iface))?;
+
         let (resp_tx, resp_rx) = oneshot::channel();
         let cmd = Command::Unload {
             id,
@@ -178,8 +282,16 @@ impl Loader for BpfdLoader {
             xdp_mode: String::new(),
             results: vec![],
         };
+        let username = request
+            .extensions()
+            .get::<User>()
+            .unwrap_or(&DEFAULT_USER)
+            .username
+            .to_string();
         let request = request.into_inner();
 
+        policy::authorize(&username, policy::Operation::List, Some(&request.iface))?;
+
         let (resp_tx, resp_rx) = oneshot::channel();
         let cmd = Command::List {
             iface: request.iface,
@@ -221,6 +333,42 @@ impl Loader for BpfdLoader {
             }
         }
     }
+
+    /// Tails the `PerfEventArray`/`RingBuf` map of a loaded program. The bpf
+    /// manager does the actual reading (one task per CPU); this handler just
+    /// asks for the receiving end of that pipe and drains it into the
+    /// streaming gRPC response, ending the stream when the program is
+    /// unloaded or the client disconnects and drops its side.
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let request = request.into_inner();
+        let id = request
+            .id
+            .parse()
+            .map_err(|_| Status::invalid_argument("invalid id"))?;
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Subscribe { id, responder: resp_tx };
+
+        let tx = self.tx.lock().unwrap().clone();
+        tx.send(cmd).await.unwrap();
+
+        let events = match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFD subscribe error: {}", e);
+                Status::aborted(format!("{e}"))
+            })?,
+            Err(e) => {
+                warn!("RPC subscribe error: {}", e);
+                return Err(Status::aborted(format!("{e}")));
+            }
+        };
+
+        let stream = ReceiverStream::new(events).map(|event| Ok(event.into()));
+        Ok(Response::new(Box::pin(stream)))
+    }
 }
 
 /// Multiple different commands are multiplexed over a single channel.
@@ -246,4 +394,16 @@ pub(crate) enum Command {
         iface: String,
         responder: Responder<Result<InterfaceInfo, BpfdError>>,
     },
+    /// Tail a loaded program's event map. The manager opens the map as an
+    /// `AsyncPerfEventArray`, spawns one reader task per `online_cpus()`
+    /// entry each looping on `buf.read_events()` over a pool of `BytesMut`
+    /// buffers sized from the map's value size, and forwards what it reads
+    /// as [`Event`]s. All reader tasks are aborted - and the
+    /// `AsyncPerfEventArray` itself dropped - as soon as `id` is unloaded or
+    /// the receiver above is dropped (client disconnected), rather than
+    /// waiting for a task to notice its channel is closed.
+    Subscribe {
+        id: Uuid,
+        responder: Responder<Result<mpsc::Receiver<Event>, BpfdError>>,
+    },
 }
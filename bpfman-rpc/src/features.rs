@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Kernel capability probes backing [`crate::rpc::BpfmanLoader::get_features`].
+//!
+//! The perf-link, program-name and `bpf_probe_read_kernel` probes use aya's
+//! own feature checks. The four BTF probes (func, global func, float, type
+//! tag) only check that the kernel's BTF can be read from sysfs; they load
+//! no BTF exercising the specific kind, so each reports the same value as
+//! `btf_supported`.
+
+use std::sync::OnceLock;
+
+use aya::Btf;
+use bpfman_api::v1::GetFeaturesResponse;
+
+static FEATURES: OnceLock<GetFeaturesResponse> = OnceLock::new();
+
+/// Returns the process-lifetime cache of kernel feature probe results,
+/// running the probes on first access.
+pub(crate) fn get_features() -> GetFeaturesResponse {
+    FEATURES.get_or_init(probe_features).clone()
+}
+
+fn probe_features() -> GetFeaturesResponse {
+    GetFeaturesResponse {
+        kernel_version: kernel_version(),
+        btf_supported: Btf::from_sys_fs().is_ok(),
+        btf_func_supported: probe_btf_func(),
+        btf_global_func_supported: probe_btf_global_func(),
+        btf_float_supported: probe_btf_float(),
+        btf_type_tag_supported: probe_btf_type_tag(),
+        perf_link_supported: probe_perf_link(),
+        prog_name_supported: probe_prog_name(),
+        probe_read_kernel_supported: probe_read_kernel(),
+    }
+}
+
+/// Parses `/proc/sys/kernel/osrelease` (e.g. "6.5.0-generic") into a
+/// dotted-triple string, falling back to "unknown" if it can't be read.
+fn kernel_version() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|s| {
+            s.trim()
+                .split(|c: char| !c.is_ascii_digit() && c != '.')
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+// The BTF probes below only check that system BTF is readable; the last
+// three defer to aya's feature checks. Any failure reads as "unsupported".
+
+fn probe_btf_func() -> bool {
+    aya::Btf::from_sys_fs().is_ok()
+}
+
+fn probe_btf_global_func() -> bool {
+    aya::Btf::from_sys_fs().is_ok()
+}
+
+fn probe_btf_float() -> bool {
+    aya::Btf::from_sys_fs().is_ok()
+}
+
+fn probe_btf_type_tag() -> bool {
+    aya::Btf::from_sys_fs().is_ok()
+}
+
+fn probe_perf_link() -> bool {
+    aya::is_perf_link_supported()
+}
+
+fn probe_prog_name() -> bool {
+    aya::is_program_name_supported()
+}
+
+fn probe_read_kernel() -> bool {
+    aya::is_probe_read_kernel_supported()
+}
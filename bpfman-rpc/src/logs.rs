@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Decoder for the wire format `aya-log` writes into a program's `AYA_LOGS`
+//! perf/ring-buffer map, used by [`crate::rpc::BpfmanLoader::get_logs`] to
+//! turn raw buffer records into [`bpfman_api::v1::LogRecord`] messages.
+
+use bpfman_api::v1::{log_record::Level as RpcLevel, LogRecord};
+
+/// Maximum size of a single encoded log record. Records larger than this are
+/// never written by `aya-log`, so anything bigger indicates buffer corruption.
+pub(crate) const LOG_BUF_CAPACITY: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum RecordField {
+    Target = 1,
+    Level = 2,
+    Module = 3,
+    File = 4,
+    Line = 5,
+    NumArgs = 6,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum DisplayHint {
+    Default = 1,
+    LowerHex = 2,
+    UpperHex = 3,
+    Ipv4 = 4,
+    Ipv6 = 5,
+    LowerMac = 6,
+    UpperMac = 7,
+}
+
+impl DisplayHint {
+    fn from_u8(v: u8) -> Option<Self> {
+        Some(match v {
+            1 => DisplayHint::Default,
+            2 => DisplayHint::LowerHex,
+            3 => DisplayHint::UpperHex,
+            4 => DisplayHint::Ipv4,
+            5 => DisplayHint::Ipv6,
+            6 => DisplayHint::LowerMac,
+            7 => DisplayHint::UpperMac,
+            _ => return None,
+        })
+    }
+}
+
+fn level_from_u8(v: u8) -> RpcLevel {
+    match v {
+        1 => RpcLevel::Error,
+        2 => RpcLevel::Warn,
+        3 => RpcLevel::Info,
+        4 => RpcLevel::Debug,
+        _ => RpcLevel::Trace,
+    }
+}
+
+/// Read a `(tag: u8, len: u16)` header followed by `len` bytes, returning the
+/// payload and the number of bytes consumed.
+fn read_field<'a>(buf: &'a [u8]) -> Option<(u8, &'a [u8], usize)> {
+    if buf.len() < 3 {
+        return None;
+    }
+    let tag = buf[0];
+    let len = u16::from_le_bytes([buf[1], buf[2]]) as usize;
+    let start = 3;
+    let end = start.checked_add(len)?;
+    if end > buf.len() {
+        return None;
+    }
+    Some((tag, &buf[start..end], end))
+}
+
+fn format_argument(hint: DisplayHint, bytes: &[u8]) -> String {
+    match hint {
+        DisplayHint::Default => String::from_utf8_lossy(bytes).to_string(),
+        DisplayHint::LowerHex => bytes.iter().map(|b| format!("{b:02x}")).collect(),
+        DisplayHint::UpperHex => bytes.iter().map(|b| format!("{b:02X}")).collect(),
+        DisplayHint::Ipv4 if bytes.len() == 4 => {
+            format!("{}.{}.{}.{}", bytes[0], bytes[1], bytes[2], bytes[3])
+        }
+        DisplayHint::Ipv6 if bytes.len() == 16 => bytes
+            .chunks(2)
+            .map(|c| format!("{:02x}{:02x}", c[0], c[1]))
+            .collect::<Vec<_>>()
+            .join(":"),
+        DisplayHint::LowerMac if bytes.len() == 6 => bytes
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(":"),
+        DisplayHint::UpperMac if bytes.len() == 6 => bytes
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(":"),
+        // Address family hints whose byte length didn't match what we expect;
+        // fall back to the raw hex rendering rather than dropping the field.
+        _ => bytes.iter().map(|b| format!("{b:02x}")).collect(),
+    }
+}
+
+/// Decode one aya-log record out of `buf`, returning the populated
+/// [`LogRecord`]. Unknown header fields are skipped so newer producers don't
+/// break older consumers of this decoder.
+pub(crate) fn decode_record(buf: &[u8]) -> Option<LogRecord> {
+    if buf.len() > LOG_BUF_CAPACITY {
+        return None;
+    }
+
+    let mut target = String::new();
+    let mut level = RpcLevel::Info;
+    let mut module = String::new();
+    let mut file = String::new();
+    let mut line = 0u32;
+    let mut message = String::new();
+
+    let mut pos = 0;
+    while pos < buf.len() {
+        let (tag, payload, consumed) = read_field(&buf[pos..])?;
+        pos += consumed;
+
+        if tag == RecordField::Target as u8 {
+            target = String::from_utf8_lossy(payload).to_string();
+        } else if tag == RecordField::Level as u8 {
+            level = payload.first().copied().map(level_from_u8).unwrap_or(level);
+        } else if tag == RecordField::Module as u8 {
+            module = String::from_utf8_lossy(payload).to_string();
+        } else if tag == RecordField::File as u8 {
+            file = String::from_utf8_lossy(payload).to_string();
+        } else if tag == RecordField::Line as u8 {
+            if payload.len() == 4 {
+                line = u32::from_le_bytes(payload.try_into().unwrap());
+            }
+        } else if tag == RecordField::NumArgs as u8 {
+            // Arguments follow as (DisplayHint, payload) pairs.
+            let num_args = payload.first().copied().unwrap_or(0);
+            for _ in 0..num_args {
+                let (hint_tag, arg_bytes, arg_consumed) = read_field(&buf[pos..])?;
+                pos += arg_consumed;
+                let hint = DisplayHint::from_u8(hint_tag).unwrap_or(DisplayHint::Default);
+                if !message.is_empty() {
+                    message.push(' ');
+                }
+                message.push_str(&format_argument(hint, arg_bytes));
+            }
+        }
+        // Unknown tags are simply skipped: the length prefix already let us
+        // advance past them above.
+    }
+
+    Some(LogRecord {
+        target,
+        level: level as i32,
+        module,
+        file,
+        line,
+        message,
+    })
+}
@@ -2,8 +2,10 @@
 // Copyright Authors of bpfman
 use bpfman::{
     command::{
-        FentryProgram, FexitProgram, KprobeProgram, ListFilter, Location, Program, ProgramData,
-        TcProgram, TracepointProgram, UprobeProgram, XdpProgram,
+        CgroupDeviceProgram, CgroupSkbProgram, CgroupSockAddrProgram, CgroupSockProgram,
+        CgroupSockoptProgram, CgroupSysctlProgram, FentryProgram, FexitProgram, KprobeProgram,
+        ListFilter, Location, Program, ProgramData, TcProgram, TracepointProgram, UprobeProgram,
+        UsdtProgram, XdpProgram,
     },
     BpfManager,
 };
@@ -11,15 +13,79 @@ use bpfman_api::{
     config::Config,
     v1::{
         attach_info::Info, bpfman_server::Bpfman, bytecode_location::Location as RpcLocation,
-        list_response::ListResult, FentryAttachInfo, FexitAttachInfo, GetRequest, GetResponse,
-        KprobeAttachInfo, ListRequest, ListResponse, LoadRequest, LoadResponse,
-        PullBytecodeRequest, PullBytecodeResponse, TcAttachInfo, TracepointAttachInfo,
-        UnloadRequest, UnloadResponse, UprobeAttachInfo, XdpAttachInfo,
+        list_response::ListResult, CgroupDeviceAttachInfo, CgroupSkbAttachInfo,
+        CgroupSockAddrAttachInfo, CgroupSockAttachInfo, CgroupSockoptAttachInfo,
+        CgroupSysctlAttachInfo, FentryAttachInfo, FexitAttachInfo, GetFeaturesRequest,
+        GetFeaturesResponse, GetLogsRequest, GetRequest, GetResponse, KprobeAttachInfo,
+        ListRequest, ListResponse, LoadRequest, LoadResponse, LogRecord, PullBytecodeRequest,
+        PullBytecodeResponse, TcAttachInfo, TracepointAttachInfo, UnloadRequest, UnloadResponse,
+        UprobeAttachInfo, UsdtAttachInfo, XdpAttachInfo,
     },
     TcProceedOn, XdpProceedOn,
 };
+use std::pin::Pin;
+
+use aya::maps::{perf::AsyncPerfEventArray, MapData};
+use bytes::BytesMut;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
 use tonic::{Request, Response, Status};
 
+use crate::{
+    features::get_features,
+    logs::{decode_record, LOG_BUF_CAPACITY},
+    serve::User,
+};
+
+const AYA_LOGS_MAP_NAME: &str = "AYA_LOGS";
+
+/// Reserved metadata keys bpfman stamps onto a program at load time with the
+/// loading caller's identity, so `unload`/`list` can later tell whether a
+/// caller is touching a program they themselves loaded. There's no dedicated
+/// owner field on `ProgramData`, so this piggybacks on the metadata map
+/// already used for user-supplied labels. `OWNER_UID_METADATA_KEY` is only
+/// meaningful for local (Unix-socket) callers; `OWNER_USERNAME_METADATA_KEY`
+/// is what mTLS callers, who have no real uid, are checked against.
+const OWNER_UID_METADATA_KEY: &str = "bpfman.io/owner-uid";
+const OWNER_USERNAME_METADATA_KEY: &str = "bpfman.io/owner-username";
+
+/// Local (Unix-socket) non-root callers may only unload or see programs they
+/// loaded themselves, identified by uid. mTLS callers have no real uid --
+/// `User::uid` is always `0` for them -- so they're never granted the local
+/// root bypass and are instead checked against the owner username recorded
+/// at load time. Only a local caller's `uid == 0` is treated as actual root.
+fn authorize_owner(program: &Program, caller: &User) -> Result<(), Status> {
+    if caller.local && caller.uid == 0 {
+        return Ok(());
+    }
+    if !caller.local {
+        let owner = program
+            .data()
+            .metadata()
+            .get(OWNER_USERNAME_METADATA_KEY)
+            .map(String::as_str);
+        return if owner == Some(caller.username.as_str()) {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(
+                "only the user who loaded this program may unload or view it",
+            ))
+        };
+    }
+    let owner = program
+        .data()
+        .metadata()
+        .get(OWNER_UID_METADATA_KEY)
+        .and_then(|uid| uid.parse::<u32>().ok());
+    if owner == Some(caller.uid) {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(
+            "only the user who loaded this program may unload or view it",
+        ))
+    }
+}
+
 pub struct BpfmanLoader {
     config: Config,
 }
@@ -32,8 +98,79 @@ impl BpfmanLoader {
 
 #[tonic::async_trait]
 impl Bpfman for BpfmanLoader {
+    type GetLogsStream = Pin<Box<dyn Stream<Item = Result<LogRecord, Status>> + Send + 'static>>;
+
+    async fn get_logs(
+        &self,
+        request: Request<GetLogsRequest>,
+    ) -> Result<Response<Self::GetLogsStream>, Status> {
+        let mut bpf_manager = BpfManager::new(self.config.clone());
+        let request = request.into_inner();
+
+        let program = bpf_manager
+            .get_program(request.id)
+            .map_err(|e| Status::aborted(format!("{e}")))?;
+
+        let map_pin_path = program
+            .data()
+            .map_pin_path()
+            .ok_or_else(|| Status::aborted("program has no associated map pin path"))?;
+        let logs_path = format!("{map_pin_path}/{AYA_LOGS_MAP_NAME}");
+
+        let map_data = MapData::from_pin(&logs_path)
+            .map_err(|e| Status::aborted(format!("failed to open {AYA_LOGS_MAP_NAME}: {e}")))?;
+        let mut perf_array = AsyncPerfEventArray::try_from(map_data)
+            .map_err(|e| Status::aborted(format!("{AYA_LOGS_MAP_NAME} is not a perf array: {e}")))?;
+
+        let (tx, rx) = mpsc::channel(128);
+
+        for cpu_id in aya::util::online_cpus()
+            .map_err(|e| Status::aborted(format!("failed to list online cpus: {e}")))?
+        {
+            let mut buf = perf_array
+                .open(cpu_id, None)
+                .map_err(|e| Status::aborted(format!("failed to open perf buffer: {e}")))?;
+            let tx = tx.clone();
+
+            tokio::spawn(async move {
+                let mut buffers = (0..10)
+                    .map(|_| BytesMut::with_capacity(LOG_BUF_CAPACITY))
+                    .collect::<Vec<_>>();
+
+                loop {
+                    let events = match buf.read_events(&mut buffers).await {
+                        Ok(events) => events,
+                        Err(_) => break,
+                    };
+                    for buffer in buffers.iter_mut().take(events.read) {
+                        if let Some(record) = decode_record(buffer) {
+                            if tx.send(Ok(record)).await.is_err() {
+                                // Client disconnected; stop reading this CPU's buffer.
+                                return;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        // Drop our own sender so the stream ends once every per-CPU task
+        // above has exited (e.g. when the program is unloaded).
+        drop(tx);
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn get_features(
+        &self,
+        _request: Request<GetFeaturesRequest>,
+    ) -> Result<Response<GetFeaturesResponse>, Status> {
+        Ok(Response::new(get_features()))
+    }
+
     async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
         let mut bpf_manager = BpfManager::new(self.config.clone());
+        let caller = request.extensions().get::<User>().cloned();
         let request = request.into_inner();
 
         let bytecode_source = match request
@@ -46,10 +183,30 @@ impl Bpfman for BpfmanLoader {
             RpcLocation::File(p) => Location::File(p),
         };
 
+        // Stamp the loading caller's identity onto the program's metadata so
+        // `unload`/`list` can later enforce that non-owning callers don't
+        // touch programs they didn't load; see `authorize_owner`. Both keys
+        // are recorded since a later `unload`/`list` caller might arrive
+        // over either listener.
+        let mut metadata = request.metadata;
+        if let Some(user) = &caller {
+            metadata.insert(OWNER_UID_METADATA_KEY.to_string(), user.uid.to_string());
+            metadata.insert(
+                OWNER_USERNAME_METADATA_KEY.to_string(),
+                user.username.clone(),
+            );
+        }
+
+        // `request.name` is the name bpfman stores the loaded program under;
+        // `request.function_name` (optional) picks which function/symbol to
+        // load out of a multi-program object, resolved against the object's
+        // symbol table by `new_pre_load`. Single-program objects can leave it
+        // unset and keep relying on `name`.
         let data = ProgramData::new_pre_load(
             bytecode_source,
             request.name,
-            request.metadata,
+            request.function_name,
+            metadata,
             request.global_data,
             request.map_owner_id,
         )
@@ -130,6 +287,68 @@ impl Bpfman for BpfmanLoader {
                 FexitProgram::new(data, fn_name)
                     .map_err(|e| Status::aborted(format!("failed to create fexitprogram: {e}")))?,
             ),
+            Info::UsdtAttachInfo(UsdtAttachInfo {
+                binary_path,
+                provider,
+                name,
+                pid,
+                container_pid,
+            }) => Program::Usdt(
+                UsdtProgram::new(data, binary_path, provider, name, pid, container_pid)
+                    .map_err(|e| Status::aborted(format!("failed to create usdtprogram: {e}")))?,
+            ),
+            Info::CgroupSkbAttachInfo(CgroupSkbAttachInfo {
+                cgroup_path,
+                direction,
+                attach_flags,
+            }) => Program::CgroupSkb(
+                CgroupSkbProgram::new(data, cgroup_path, direction, attach_flags)
+                    .map_err(|e| Status::aborted(format!("failed to create cgroupskbprogram: {e}")))?,
+            ),
+            Info::CgroupSockAttachInfo(CgroupSockAttachInfo {
+                cgroup_path,
+                attach_type,
+                attach_flags,
+            }) => Program::CgroupSock(
+                CgroupSockProgram::new(data, cgroup_path, attach_type, attach_flags).map_err(
+                    |e| Status::aborted(format!("failed to create cgroupsockprogram: {e}")),
+                )?,
+            ),
+            Info::CgroupSockAddrAttachInfo(CgroupSockAddrAttachInfo {
+                cgroup_path,
+                attach_type,
+                attach_flags,
+            }) => Program::CgroupSockAddr(
+                CgroupSockAddrProgram::new(data, cgroup_path, attach_type, attach_flags).map_err(
+                    |e| Status::aborted(format!("failed to create cgroupsockaddrprogram: {e}")),
+                )?,
+            ),
+            Info::CgroupSockoptAttachInfo(CgroupSockoptAttachInfo {
+                cgroup_path,
+                attach_type,
+                attach_flags,
+            }) => Program::CgroupSockopt(
+                CgroupSockoptProgram::new(data, cgroup_path, attach_type, attach_flags).map_err(
+                    |e| Status::aborted(format!("failed to create cgroupsockoptprogram: {e}")),
+                )?,
+            ),
+            Info::CgroupSysctlAttachInfo(CgroupSysctlAttachInfo {
+                cgroup_path,
+                sysctl,
+                attach_flags,
+            }) => Program::CgroupSysctl(
+                CgroupSysctlProgram::new(data, cgroup_path, sysctl, attach_flags).map_err(|e| {
+                    Status::aborted(format!("failed to create cgroupsysctlprogram: {e}"))
+                })?,
+            ),
+            Info::CgroupDeviceAttachInfo(CgroupDeviceAttachInfo {
+                cgroup_path,
+                attach_flags,
+            }) => Program::CgroupDevice(
+                CgroupDeviceProgram::new(data, cgroup_path, attach_flags).map_err(|e| {
+                    Status::aborted(format!("failed to create cgroupdeviceprogram: {e}"))
+                })?,
+            ),
         };
 
         let program = bpf_manager
@@ -155,10 +374,18 @@ impl Bpfman for BpfmanLoader {
         request: Request<UnloadRequest>,
     ) -> Result<Response<UnloadResponse>, Status> {
         let mut bpf_manager = BpfManager::new(self.config.clone());
+        let caller = request.extensions().get::<User>().cloned();
 
         let reply = UnloadResponse {};
         let request = request.into_inner();
 
+        if let Some(user) = &caller {
+            let program = bpf_manager
+                .get_program(request.id)
+                .map_err(|e| Status::aborted(format!("{e}")))?;
+            authorize_owner(&program, user)?;
+        }
+
         bpf_manager
             .remove_program(request.id)
             .await
@@ -204,6 +431,7 @@ impl Bpfman for BpfmanLoader {
 
     async fn list(&self, request: Request<ListRequest>) -> Result<Response<ListResponse>, Status> {
         let mut bpf_manager = BpfManager::new(self.config.clone());
+        let caller = request.extensions().get::<User>().cloned();
 
         let mut reply = ListResponse { results: vec![] };
 
@@ -215,6 +443,15 @@ impl Bpfman for BpfmanLoader {
 
         // Await the response
         for r in bpf_manager.list_programs(filter) {
+            // Kernel-loaded (non-bpfman) programs have no owner to check;
+            // everything else is hidden from non-root callers who didn't
+            // load it themselves.
+            if let Some(user) = &caller {
+                if !matches!(r, Program::Unsupported(_)) && authorize_owner(&r, user).is_err() {
+                    continue;
+                }
+            }
+
             // Populate the response with the Program Info and the Kernel Info.
             let reply_entry = ListResult {
                 info: if let Program::Unsupported(_) = r {
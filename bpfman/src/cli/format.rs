@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Output format shared by the CLI subcommands, so automation can consume
+//! bpfman's output without scraping the human-oriented tables.
+
+use bpfman_api::v1::{KernelProgramInfo, ProgramInfo};
+use serde::Serialize;
+
+use crate::errors::BpfmanError;
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// The program/kernel info a `get` renders, serialized verbatim under
+/// `--format json` instead of going through [`crate::cli::table::ProgTable`].
+#[derive(Serialize)]
+pub(crate) struct GetOutput<'a> {
+    pub(crate) info: &'a Option<ProgramInfo>,
+    pub(crate) kernel_info: &'a Option<KernelProgramInfo>,
+}
+
+/// Prints a command failure in the requested format: a plain log-style line
+/// for `Table`, or a stable `{"error": "..."}` object for `Json` so callers
+/// never have to parse mixed human/machine output on the failure path.
+pub(crate) fn print_error(e: &BpfmanError, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            let value = serde_json::json!({ "error": e.to_string() });
+            println!("{}", serde_json::to_string(&value).unwrap_or_default());
+        }
+        OutputFormat::Table => log::warn!("BPFMAN error: {}", e),
+    }
+}
@@ -2,16 +2,23 @@
 // Copyright Authors of bpfman
 
 use bpfman_api::v1::{KernelProgramInfo, ProgramInfo};
-use log::warn;
 
 use crate::{
     bpf::BpfManager,
-    cli::{args::GetArgs, table::ProgTable},
+    cli::{
+        args::GetArgs,
+        format::{print_error, GetOutput, OutputFormat},
+        table::ProgTable,
+    },
     command::Program,
     errors::BpfmanError,
 };
 
-pub(crate) fn execute_get(bpf_manager: &mut BpfManager, args: &GetArgs) -> Result<(), BpfmanError> {
+pub(crate) fn execute_get(
+    bpf_manager: &mut BpfManager,
+    args: &GetArgs,
+    format: OutputFormat,
+) -> Result<(), BpfmanError> {
     match bpf_manager.get_program(args.id) {
         Ok(program) => {
             let info: Option<ProgramInfo> = if let Program::Unsupported(_) = program {
@@ -29,12 +36,23 @@ pub(crate) fn execute_get(bpf_manager: &mut BpfManager, args: &GetArgs) -> Resul
                 Err(e) => return Err(e),
             };
 
-            ProgTable::new_get_bpfman(&info)?.print();
-            ProgTable::new_get_unsupported(&kernel_info)?.print();
+            match format {
+                OutputFormat::Table => {
+                    ProgTable::new_get_bpfman(&info)?.print();
+                    ProgTable::new_get_unsupported(&kernel_info)?.print();
+                }
+                OutputFormat::Json => {
+                    let output = GetOutput {
+                        info: &info,
+                        kernel_info: &kernel_info,
+                    };
+                    println!("{}", serde_json::to_string(&output).unwrap_or_default());
+                }
+            }
             Ok(())
         }
         Err(e) => {
-            warn!("BPFMAN get error: {}", e);
+            print_error(&e, format);
             Err(e)
         }
     }
@@ -1,13 +1,48 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context};
 use base64::{engine::general_purpose, Engine};
 use bpfman_api::ImagePullPolicy;
+use serde::Deserialize;
 
 use crate::{
     bpf::BpfManager, cli::args::{ImageSubCommand, PullBytecodeArgs}, command, oci_utils::image_manager::BytecodeImage
 };
 
+/// Deserialized shape of a Docker/podman `config.json`, restricted to the
+/// fields needed to resolve credentials for a registry host.
+#[derive(Debug, Default, Deserialize)]
+struct DockerConfig {
+    #[serde(default)]
+    auths: HashMap<String, DockerConfigAuth>,
+    #[serde(rename = "credsStore", default)]
+    creds_store: Option<String>,
+    #[serde(rename = "credHelpers", default)]
+    cred_helpers: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DockerConfigAuth {
+    auth: Option<String>,
+}
+
+/// Output of the `docker-credential-<helper> get` protocol.
+#[derive(Debug, Deserialize)]
+struct CredentialHelperOutput {
+    #[serde(rename = "Username")]
+    username: String,
+    #[serde(rename = "Secret")]
+    secret: String,
+}
+
 impl ImageSubCommand {
     pub(crate) fn execute(&self, bpf_manager: &mut BpfManager) -> anyhow::Result<()> {
         match self {
@@ -16,20 +51,129 @@ impl ImageSubCommand {
     }
 }
 
+/// Returns the registry host portion of an OCI image reference, e.g.
+/// `quay.io/bpfman-bytecode/xdp_pass:latest` -> `quay.io`. Images without an
+/// explicit registry (a bare `name:tag`) resolve to Docker Hub.
+fn registry_host(image_url: &str) -> &str {
+    let without_tag = image_url.split('@').next().unwrap_or(image_url);
+    let Some((first_segment, _)) = without_tag.split_once('/') else {
+        return "index.docker.io";
+    };
+    if first_segment.contains('.') || first_segment.contains(':') || first_segment == "localhost" {
+        first_segment
+    } else {
+        "index.docker.io"
+    }
+}
+
+/// Locates the `config.json` to read registry credentials from, preferring
+/// an explicit `config_path`, then `$DOCKER_CONFIG/config.json`, then
+/// `~/.docker/config.json`.
+fn default_config_path(config_path: &Option<String>) -> Option<PathBuf> {
+    if let Some(p) = config_path {
+        return Some(PathBuf::from(p));
+    }
+    if let Ok(dir) = std::env::var("DOCKER_CONFIG") {
+        return Some(Path::new(&dir).join("config.json"));
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| Path::new(&home).join(".docker").join("config.json"))
+}
+
+fn load_docker_config(config_path: &Option<String>) -> anyhow::Result<DockerConfig> {
+    let Some(path) = default_config_path(config_path) else {
+        return Ok(DockerConfig::default());
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse docker config at {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DockerConfig::default()),
+        Err(e) => Err(e).with_context(|| format!("failed to read docker config at {}", path.display())),
+    }
+}
+
+/// Invokes `docker-credential-<helper> get`, writing `host` on stdin and
+/// parsing the `{"Username","Secret"}` JSON response on stdout, per the
+/// Docker credential-helper protocol.
+fn run_credential_helper(helper: &str, host: &str) -> anyhow::Result<(String, String)> {
+    let mut child = Command::new(format!("docker-credential-{helper}"))
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run docker-credential-{helper}"))?;
+
+    child
+        .stdin
+        .take()
+        .context("credential helper stdin unavailable")?
+        .write_all(host.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "docker-credential-{helper} get {host} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let parsed: CredentialHelperOutput = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("failed to parse docker-credential-{helper} output"))?;
+    Ok((parsed.username, parsed.secret))
+}
+
+/// Resolves credentials for `image_url`'s registry host, preferring (in
+/// order) a matching `auths` entry in the docker config, a per-registry
+/// `credHelpers` entry, the global `credsStore`, and finally the explicit
+/// base64 `username:password` blob passed via `registry_auth`.
+fn resolve_registry_auth(
+    image_url: &str,
+    registry_auth: &Option<String>,
+    config_path: &Option<String>,
+) -> anyhow::Result<(Option<String>, Option<String>)> {
+    let host = registry_host(image_url);
+    let config = load_docker_config(config_path)?;
+
+    if let Some(auth) = config.auths.get(host).and_then(|a| a.auth.as_ref()) {
+        let auth_raw = general_purpose::STANDARD.decode(auth)?;
+        let auth_string = String::from_utf8(auth_raw)?;
+        let (username, password) = auth_string
+            .split_once(':')
+            .context("malformed auth entry in docker config")?;
+        return Ok((Some(username.to_owned()), Some(password.to_owned())));
+    }
+
+    let helper = config.cred_helpers.get(host).or(config.creds_store.as_ref());
+    if let Some(helper) = helper {
+        let (username, password) = run_credential_helper(helper, host)?;
+        return Ok((Some(username), Some(password)));
+    }
+
+    match registry_auth {
+        Some(a) => {
+            let auth_raw = general_purpose::STANDARD.decode(a)?;
+            let auth_string = String::from_utf8(auth_raw)?;
+            let (username, password) = auth_string
+                .split_once(':')
+                .context("malformed registry_auth, expected username:password")?;
+            Ok((Some(username.to_owned()), Some(password.to_owned())))
+        }
+        None => Ok((None, None)),
+    }
+}
+
 impl TryFrom<&PullBytecodeArgs> for BytecodeImage {
     type Error = anyhow::Error;
 
     fn try_from(value: &PullBytecodeArgs) -> Result<Self, Self::Error> {
         let image_pull_policy: ImagePullPolicy = value.pull_policy.as_str().try_into()?;
-        let (username, password) = match &value.registry_auth {
-            Some(a) => {
-                let auth_raw = general_purpose::STANDARD.decode(a)?;
-                let auth_string = String::from_utf8(auth_raw)?;
-                let (username, password) = auth_string.split_once(':').unwrap();
-                (Some(username.to_owned()), Some(password.to_owned()))
-            }
-            None => (None, None),
-        };
+        let (username, password) = resolve_registry_auth(
+            &value.image_url,
+            &value.registry_auth,
+            &value.registry_config_path,
+        )?;
 
         Ok(BytecodeImage {
             image_url: value.image_url.clone(),
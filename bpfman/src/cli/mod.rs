@@ -2,6 +2,7 @@
 // Copyright Authors of bpfman
 
 pub(crate) mod args;
+pub(crate) mod format;
 mod get;
 mod image;
 mod list;
@@ -12,6 +13,7 @@ mod unload;
 
 use args::Commands;
 use bpfman_api::{config::Config, util::directories::RTPATH_BPFMAN_SOCKET};
+use format::OutputFormat;
 use get::execute_get;
 use list::execute_list;
 use log::warn;
@@ -21,12 +23,16 @@ use tower::service_fn;
 use unload::execute_unload;
 
 impl Commands {
-    pub(crate) async fn execute(&self, config: Config) -> Result<(), anyhow::Error> {
+    pub(crate) async fn execute(
+        &self,
+        config: Config,
+        format: OutputFormat,
+    ) -> Result<(), anyhow::Error> {
         match self {
             Commands::Load(l) => l.execute().await,
             Commands::Unload(args) => execute_unload(args).await,
-            Commands::List(args) => execute_list(args).await,
-            Commands::Get(args) => execute_get(args).await,
+            Commands::List(args) => execute_list(args, format).await,
+            Commands::Get(args) => execute_get(args, format).await,
             Commands::Image(i) => i.execute().await,
             Commands::System(s) => s.execute(&config).await,
         }
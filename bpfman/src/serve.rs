@@ -1,32 +1,164 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{fs::remove_file, path::Path};
+use std::{
+    fs::remove_file,
+    io,
+    net::SocketAddr,
+    os::unix::io::AsRawFd,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 use bpfman_api::{
-    config::Config, util::directories::RTPATH_BPFMAN_SOCKET, v1::bpfman_server::BpfmanServer,
+    config::{Config, Endpoint},
+    util::directories::RTPATH_BPFMAN_SOCKET,
+    v1::bpfman_server::BpfmanServer,
 };
 use log::{debug, info};
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
 use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
     join,
-    net::UnixListener,
+    net::{UnixListener, UnixStream},
     select,
     signal::unix::{signal, SignalKind},
     sync::mpsc,
     task::JoinHandle,
 };
-use tokio_stream::wrappers::UnixListenerStream;
-use tonic::transport::Server;
+use tokio_stream::{wrappers::UnixListenerStream, StreamExt};
+use tonic::{
+    transport::{server::Connected, Certificate, Identity, Server, ServerTlsConfig},
+    Request, Status,
+};
+use users::get_user_by_uid;
+use x509_certificate::X509Certificate;
 
 use crate::{
     bpf::BpfManager,
     oci_utils::ImageManager,
     rpc::BpfmanLoader,
+    static_programs,
     storage::StorageManager,
     utils::{set_file_permissions, SOCK_MODE},
     ROOT_DB,
 };
 
+/// The caller that made an RPC, authenticated either as a local user over
+/// the Unix socket (`local: true`, `uid`/`gid` from `SO_PEERCRED`) or as a
+/// certificate CN over the mTLS listener (`local: false`, `uid`/`gid` left
+/// at `0` since a TLS client isn't a local user). Handlers must check
+/// `local` before treating `uid == 0` as root: a TLS caller's `uid` is
+/// always `0` and carries no privilege, so only a local caller's `uid == 0`
+/// means actual root. Non-bypassing authorization compares `uid` for local
+/// callers and `username` for TLS callers; see `authorize_owner` in
+/// bpfman-rpc.
+#[derive(Debug, Clone)]
+pub(crate) struct User {
+    pub(crate) username: String,
+    pub(crate) uid: u32,
+    pub(crate) gid: u32,
+    pub(crate) local: bool,
+}
+
+/// Wraps an accepted [`UnixStream`] so tonic's transport records the peer's
+/// `SO_PEERCRED` credentials as connection info. Every request made over
+/// this connection then carries `ConnectInfo<Option<User>>` in its
+/// extensions, which [`authenticate_uds_peer`] promotes to a plain `User`.
+struct UdsConnection(UnixStream);
+
+impl Connected for UdsConnection {
+    type ConnectInfo = Option<User>;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        let cred = getsockopt(&self.0.as_raw_fd(), PeerCredentials).ok()?;
+        let username = get_user_by_uid(cred.uid())
+            .map(|u| u.name().to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Some(User {
+            username,
+            uid: cred.uid(),
+            gid: cred.gid(),
+            local: true,
+        })
+    }
+}
+
+impl AsyncRead for UdsConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UdsConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_shutdown(cx)
+    }
+}
+
+/// Promotes the `ConnectInfo<Option<User>>` tonic recorded for this
+/// connection (see [`UdsConnection`]) into a `User` extension on the
+/// individual request, which is where the `load`/`unload`/`list` handlers
+/// look for it. Connections where peer credentials couldn't be read (e.g.
+/// not actually a Unix socket) simply leave no `User` extension behind.
+fn authenticate_uds_peer(mut req: Request<()>) -> Result<Request<()>, Status> {
+    if let Some(user) = req
+        .extensions()
+        .get::<tonic::transport::server::ConnectInfo<Option<User>>>()
+        .and_then(|info| info.get().clone())
+    {
+        req.extensions_mut().insert(user);
+    }
+    Ok(req)
+}
+
+/// Promotes the client certificate tonic recorded for a TLS connection into
+/// the same `User` extension the Unix path uses, so `load`/`unload`/`list`
+/// don't need to care which listener a request arrived on. The certificate's
+/// subject common name becomes `username`; TLS clients aren't local users, so
+/// `uid`/`gid` are left at `0` and `local` is `false`, which tells
+/// `authorize_owner` this `uid == 0` does not mean root and to compare
+/// `username` against the recorded owner instead.
+fn authenticate_tls_peer(mut req: Request<()>) -> Result<Request<()>, Status> {
+    let certs = req
+        .peer_certs()
+        .ok_or_else(|| Status::unauthenticated("no client certificate presented"))?;
+
+    let cert = certs
+        .first()
+        .and_then(|c| X509Certificate::from_der(c.get_ref()).ok())
+        .ok_or_else(|| Status::unauthenticated("invalid client certificate"))?;
+
+    let username = cert
+        .subject_common_name()
+        .ok_or_else(|| Status::unauthenticated("client certificate has no CN"))?;
+
+    req.extensions_mut().insert(User {
+        username,
+        uid: 0,
+        gid: 0,
+        local: false,
+    });
+    Ok(req)
+}
+
 pub async fn serve(config: &Config, csi_support: bool) -> anyhow::Result<()> {
     let (tx, rx) = mpsc::channel(32);
 
@@ -41,6 +173,28 @@ pub async fn serve(config: &Config, csi_support: bool) -> anyhow::Result<()> {
         Err(e) => eprintln!("Error = {e:?}"),
     }
 
+    for endpoint in config.grpc.endpoints.clone() {
+        match endpoint {
+            Endpoint::Tcp {
+                address,
+                port,
+                enabled,
+            } if !enabled => info!("Skipping disabled endpoint on {address}, port: {port}"),
+            Endpoint::Tcp {
+                address,
+                port,
+                enabled: _,
+            } => match serve_tcp(address, port, service.clone(), &config.tls).await {
+                Ok(handle) => listeners.push(handle),
+                Err(e) => eprintln!("Error = {e:?}"),
+            },
+            Endpoint::Unix { .. } => {
+                // The Unix listener above is always started regardless of
+                // config, so there's nothing further to do for this variant.
+            }
+        }
+    }
+
     let allow_unsigned = config.signing.as_ref().map_or(true, |s| s.allow_unsigned);
     let (itx, irx) = mpsc::channel(32);
 
@@ -52,20 +206,14 @@ pub async fn serve(config: &Config, csi_support: bool) -> anyhow::Result<()> {
     let mut bpf_manager = BpfManager::new(config.clone(), rx, itx);
     bpf_manager.rebuild_state().await?;
 
-    // TODO(astoycos) see issue #881
-    //let static_programs = get_static_programs(static_program_path).await?;
-
-    // Load any static programs first
-    // if !static_programs.is_empty() {
-    //     for prog in static_programs {
-    //         let ret_prog = bpf_manager.add_program(prog).await?;
-    //         // Get the Kernel Info.
-    //         let kernel_info = ret_prog
-    //             .kernel_info()
-    //             .expect("kernel info should be set for all loaded programs");
-    //         info!("Loaded static program with program id {}", kernel_info.id)
-    //     }
-    // };
+    // Load any static programs configured declaratively under
+    // /etc/bpfman/programs.d, reconciled against the state `rebuild_state`
+    // just rebuilt so restarts are idempotent. See issue #881.
+    static_programs::load_static_programs(
+        Path::new("/etc/bpfman/programs.d"),
+        &mut bpf_manager,
+    )
+    .await?;
 
     if csi_support {
         let storage_manager = StorageManager::new(tx);
@@ -125,11 +273,12 @@ async fn serve_unix(
     }
 
     let uds = UnixListener::bind(&path)?;
-    let uds_stream = UnixListenerStream::new(uds);
+    let uds_stream = UnixListenerStream::new(uds).map(|conn| conn.map(UdsConnection));
     // Always set the file permissions of our listening socket.
     set_file_permissions(&path.clone(), SOCK_MODE).await;
 
     let serve = Server::builder()
+        .layer(tonic::service::interceptor(authenticate_uds_peer))
         .add_service(service)
         .serve_with_incoming_shutdown(uds_stream, shutdown_handler());
 
@@ -141,3 +290,42 @@ async fn serve_unix(
         info!("Shutdown Unix Handler {}", path);
     }))
 }
+
+/// Binds a mutual-TLS TCP endpoint so bpfman can be driven remotely (e.g. by
+/// a Kubernetes agent) without exposing an unauthenticated socket. The
+/// daemon presents `tls.server_cert`/`tls.server_key` as its own identity,
+/// requires a client certificate signed by `tls.ca_cert`, and
+/// [`authenticate_tls_peer`] maps that certificate into the same `User`
+/// extension the Unix listener populates.
+async fn serve_tcp(
+    address: String,
+    port: u16,
+    service: BpfmanServer<BpfmanLoader>,
+    tls: &bpfman_api::config::Tls,
+) -> anyhow::Result<JoinHandle<()>> {
+    let ca_cert = tokio::fs::read(&tls.ca_cert).await?;
+    let ca_cert = Certificate::from_pem(ca_cert);
+    let cert = tokio::fs::read(&tls.server_cert).await?;
+    let key = tokio::fs::read(&tls.server_key).await?;
+    let identity = Identity::from_pem(cert, key);
+
+    let tls_config = ServerTlsConfig::new()
+        .identity(identity)
+        .client_ca_root(ca_cert);
+
+    let addr: SocketAddr = format!("{address}:{port}").parse()?;
+
+    let serve = Server::builder()
+        .tls_config(tls_config)?
+        .layer(tonic::service::interceptor(authenticate_tls_peer))
+        .add_service(service)
+        .serve_with_shutdown(addr, shutdown_handler());
+
+    Ok(tokio::spawn(async move {
+        info!("Listening on {addr}");
+        if let Err(e) = serve.await {
+            eprintln!("Error = {e:?}");
+        }
+        info!("Shutdown TCP Handler {}", addr);
+    }))
+}
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Declarative static program loading (see issue #881). `serve()` calls
+//! [`load_static_programs`] once, right after `rebuild_state()` and before
+//! the server starts accepting RPCs, so a configured directory of
+//! TOML/YAML manifests can pin a baseline set of programs independent of
+//! any live client. Reconciliation against already-loaded state makes
+//! restarts idempotent: a manifest whose name and bytecode location match a
+//! loaded program is left alone, a missing one is loaded, and a name
+//! collision against a program loaded from a different location aborts
+//! startup rather than silently overwriting it. Only the name and location
+//! are compared; an already-loaded program's attach settings and global
+//! data are not checked against the manifest.
+
+use std::{collections::HashMap, path::Path};
+
+use bpfman::{
+    command::{ListFilter, Location, Program, ProgramData, TcProgram, TracepointProgram, XdpProgram},
+    BpfManager,
+};
+use bpfman_api::{TcProceedOn, XdpProceedOn};
+use log::info;
+use serde::Deserialize;
+
+/// One entry in a static program manifest. Mirrors the subset of
+/// `LoadRequest` fields static loading supports today (XDP, TC and
+/// tracepoint); other attach types can be added here as they come up.
+#[derive(Debug, Deserialize)]
+struct StaticProgram {
+    name: String,
+    location: StaticLocation,
+    #[serde(default)]
+    function_name: Option<String>,
+    #[serde(default)]
+    global_data: HashMap<String, Vec<u8>>,
+    attach: StaticAttach,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StaticLocation {
+    Image(String),
+    File(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StaticAttach {
+    Xdp {
+        iface: String,
+        priority: i32,
+        #[serde(default)]
+        proceed_on: Vec<i32>,
+    },
+    Tc {
+        iface: String,
+        priority: i32,
+        direction: i32,
+        #[serde(default)]
+        proceed_on: Vec<i32>,
+    },
+    Tracepoint {
+        tracepoint: String,
+    },
+}
+
+/// Scans `dir` for `*.toml`/`*.yaml`/`*.yml` manifests and loads whatever
+/// isn't already present in `bpf_manager`'s rebuilt state. A missing
+/// directory is treated as "no static programs configured", not an error.
+pub(crate) async fn load_static_programs(
+    dir: &Path,
+    bpf_manager: &mut BpfManager,
+) -> anyhow::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            info!(
+                "No static program directory at {}, skipping static load",
+                dir.display()
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        let manifest = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str::<StaticProgram>(&std::fs::read_to_string(&path)?)?,
+            Some("yaml") | Some("yml") => {
+                serde_yaml::from_str::<StaticProgram>(&std::fs::read_to_string(&path)?)?
+            }
+            _ => continue,
+        };
+
+        if let Some(existing) = bpf_manager
+            .list_programs(ListFilter::new(None, HashMap::new(), true))
+            .find(|p| p.data().name() == manifest.name)
+        {
+            if existing.data().location() == manifest_location(&manifest.location) {
+                info!(
+                    "Static program {} already loaded, leaving it in place",
+                    manifest.name
+                );
+                continue;
+            }
+            anyhow::bail!(
+                "a program named {} is already loaded with a different configuration; \
+                 refusing to overwrite it with the static manifest at {}",
+                manifest.name,
+                path.display()
+            );
+        }
+
+        let data = ProgramData::new_pre_load(
+            manifest_location(&manifest.location),
+            manifest.name.clone(),
+            manifest.function_name.clone(),
+            HashMap::new(),
+            manifest.global_data.clone(),
+            None,
+        )?;
+
+        let program = match manifest.attach {
+            StaticAttach::Xdp {
+                iface,
+                priority,
+                proceed_on,
+            } => Program::Xdp(XdpProgram::new(
+                data,
+                priority,
+                iface,
+                XdpProceedOn::from_int32s(proceed_on)
+                    .map_err(|_| anyhow::anyhow!("failed to parse proceed_on"))?,
+            )?),
+            StaticAttach::Tc {
+                iface,
+                priority,
+                direction,
+                proceed_on,
+            } => Program::Tc(TcProgram::new(
+                data,
+                priority,
+                iface,
+                TcProceedOn::from_int32s(proceed_on)
+                    .map_err(|_| anyhow::anyhow!("failed to parse proceed_on"))?,
+                direction
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("direction is not a valid Tc direction"))?,
+            )?),
+            StaticAttach::Tracepoint { tracepoint } => {
+                Program::Tracepoint(TracepointProgram::new(data, tracepoint)?)
+            }
+        };
+
+        let program = bpf_manager.add_program(program).await?;
+        let kernel_info = program
+            .kernel_info()
+            .expect("kernel info should be set for all loaded programs");
+        info!(
+            "Loaded static program {} with program id {}",
+            manifest.name, kernel_info.id
+        );
+    }
+
+    Ok(())
+}
+
+fn manifest_location(location: &StaticLocation) -> Location {
+    match location {
+        StaticLocation::Image(image) => Location::Image(image.clone().into()),
+        StaticLocation::File(path) => Location::File(path.clone()),
+    }
+}